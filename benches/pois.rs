@@ -0,0 +1,141 @@
+use std::{
+    hint::black_box,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+use cess_pois_rust_ffi::{
+    ffi::PoisLibrary,
+    params::PoisParams,
+    testing::{make_commits, shaped_proofs},
+    types::{Challenge, ProverId},
+    utils::{
+        c_ptr_to_i64_array_of_array, rsa_keygen, rust_commit_array_to_commit_c_array,
+        rust_commit_proof_array_to_commit_proof_c_array,
+    },
+    views::ArrOfArrBuf,
+};
+
+// Marshalling between Rust and C, and the calls into Go.
+// The group names are what a native implementation is to be compared
+// against, so keep them stable.
+//
+//     cargo bench --bench pois -- marshal
+
+// A challenge of the shape Go generates for k = 7
+fn make_challenge(files: i64) -> Challenge {
+    let flat: Vec<Vec<i64>> = (1..=files)
+        .map(|file_index| vec![file_index, 3903, 17, 49, 28, 8, 15, 64, 29])
+        .collect();
+    Challenge::from_flat(&flat).unwrap()
+}
+
+fn marshal_commits(c: &mut Criterion) {
+    let mut group = c.benchmark_group("marshal/commits");
+    for files in [4, 16, 64] {
+        let commits = make_commits(7, files, 1);
+        group.bench_with_input(BenchmarkId::from_parameter(files), &commits, |b, commits| {
+            b.iter(|| rust_commit_array_to_commit_c_array(black_box(commits)))
+        });
+    }
+    group.finish();
+}
+
+fn marshal_challenge(c: &mut Criterion) {
+    let mut group = c.benchmark_group("marshal/challenge");
+    for files in [4, 16, 64] {
+        let challenge = ArrOfArrBuf::new(make_challenge(files).to_flat());
+        let raw = challenge.as_raw();
+        group.bench_function(BenchmarkId::from_parameter(files), |b| {
+            b.iter(|| unsafe {
+                c_ptr_to_i64_array_of_array(black_box(raw.main_array), raw.sub_array_lengths, raw.length).unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn marshal_commit_proofs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("marshal/commit_proofs");
+    for files in [1, 4, 16] {
+        let proofs = shaped_proofs(&make_challenge(files), 512, 64);
+        group.bench_with_input(BenchmarkId::new("to_c", files), &proofs, |b, proofs| {
+            b.iter(|| rust_commit_proof_array_to_commit_proof_c_array(black_box(proofs)))
+        });
+        let proofs_c = rust_commit_proof_array_to_commit_proof_c_array(&proofs);
+        group.bench_function(BenchmarkId::new("from_c", files), |b| b.iter(|| proofs_c.view().to_vec()));
+    }
+    group.finish();
+}
+
+fn keygen(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rsa_keygen");
+    group.sample_size(10);
+    for bits in [2048, 3072] {
+        group.bench_with_input(BenchmarkId::from_parameter(bits), &bits, |b, &bits| b.iter(|| rsa_keygen(bits)));
+    }
+    group.finish();
+}
+
+// A challenge for 4 files, then the verification of proofs for it.
+// Every iteration is a new miner, Go keeps the state of the previous ones.
+//
+// Only libraries reporting a verdict verify anything, see
+// PoisLibrary::can_verify. With the others only the challenge is timed.
+fn challenge_and_verify(c: &mut Criterion) {
+    static MINER: AtomicU64 = AtomicU64::new(0);
+    let next_miner = || ProverId::from(format!("bench miner {}", MINER.fetch_add(1, Ordering::Relaxed)));
+
+    let lib = PoisLibrary::load_default();
+    let mut group = c.benchmark_group("challenge");
+    group.sample_size(20);
+    for n in [512, 1024, 2048] {
+        let params = PoisParams { n, ..PoisParams::devnet() };
+        let mut common_params = params.to_common_param();
+        let commits = make_commits(params.k, 4, 1);
+        group.bench_function(BenchmarkId::from_parameter(n), |b| {
+            b.iter_batched(
+                next_miner,
+                |id| lib.generate_commit_challenge(&commits, &mut common_params, &id),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+
+    if !lib.can_verify() {
+        eprintln!("challenge_verify: skipped, the Go library reports no verdict on commit proofs");
+        return;
+    }
+    let mut group = c.benchmark_group("challenge_verify");
+    group.sample_size(20);
+    for n in [512, 1024, 2048] {
+        let params = PoisParams { n, ..PoisParams::devnet() };
+        let mut common_params = params.to_common_param();
+        let commits = make_commits(params.k, 4, 1);
+        group.bench_function(BenchmarkId::from_parameter(n), |b| {
+            b.iter_batched(
+                next_miner,
+                |id| {
+                    let challenge = lib.generate_commit_challenge(&commits, &mut common_params, &id);
+                    let proofs = shaped_proofs(&challenge, params.n, params.d);
+                    // Made up proofs, rejected by Go after a full verification
+                    let _ = lib.verify_commit_and_acc_proofs(proofs, challenge, &mut common_params, &id);
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    marshal_commits,
+    marshal_challenge,
+    marshal_commit_proofs,
+    keygen,
+    challenge_and_verify
+);
+criterion_main!(benches);
@@ -0,0 +1,213 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+fn main() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set"));
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rustc-check-cfg=cfg(pois_exports)");
+
+    // Absolute path of the Go shared library, the last place looked at for
+    // it at runtime, see ffi::DEFAULT_LIBRARY_PATH
+    let lib = go_library(&manifest_dir);
+    println!("cargo:rustc-env=CESS_POIS_LIB_PATH={}", lib.display());
+
+    #[cfg(feature = "static")]
+    link_static(&manifest_dir);
+
+    #[cfg(feature = "bindgen")]
+    bindings(&manifest_dir);
+
+    #[cfg(feature = "grpc")]
+    grpc();
+}
+
+// The prebuilt library shipped in cgo/
+#[cfg(not(feature = "build-go"))]
+fn go_library(manifest_dir: &Path) -> PathBuf {
+    manifest_dir.join("cgo/main.so")
+}
+
+// Link libpois.a, built with -buildmode=c-archive, from
+// CESS_POIS_STATIC_LIB_DIR or cgo/
+#[cfg(all(feature = "static", not(feature = "build-go")))]
+fn link_static(manifest_dir: &Path) {
+    println!("cargo:rerun-if-env-changed=CESS_POIS_STATIC_LIB_DIR");
+    let dir = env::var_os("CESS_POIS_STATIC_LIB_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| manifest_dir.join("cgo"));
+    if !dir.join("libpois.a").exists() {
+        panic!(
+            "static: no libpois.a in {}, build it with go build -buildmode=c-archive, \
+             set CESS_POIS_STATIC_LIB_DIR or enable build-go",
+            dir.display()
+        );
+    }
+    println!("cargo:rerun-if-changed={}", dir.join("libpois.a").display());
+    link_archive(&dir);
+}
+
+// The archive built with build-go
+#[cfg(all(feature = "static", feature = "build-go"))]
+fn link_static(manifest_dir: &Path) {
+    let out = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR not set"));
+    go_build(manifest_dir, "c-archive", &out.join("libpois.a"));
+    link_archive(&out);
+}
+
+// Link dir/libpois.a. Archives exporting PoisVersion, PoisSetLogger and
+// PoisLastError get the pois_exports cfg, under which ffi links those too.
+// Older archives lack them and referring to them would break their link.
+#[cfg(feature = "static")]
+fn link_archive(dir: &Path) {
+    let archive = dir.join("libpois.a");
+    let bytes = std::fs::read(&archive).unwrap_or_else(|e| panic!("static: failed to read {}: {}", archive.display(), e));
+    let symbols = archive_symbols(&bytes);
+    let has = |name: &str| symbols.iter().any(|symbol| symbol == name);
+    if ["PoisVersion", "PoisSetLogger", "PoisLastError"].iter().all(|name| has(name)) {
+        println!("cargo:rustc-cfg=pois_exports");
+    } else {
+        println!(
+            "cargo:warning=static: {} lacks PoisVersion, PoisSetLogger or PoisLastError, \
+             the version check, Go logs and Go errors are off",
+            archive.display()
+        );
+    }
+    println!("cargo:rustc-link-search=native={}", dir.display());
+    println!("cargo:rustc-link-lib=static=pois");
+}
+
+// The names in the symbol table of an ar archive as written by GNU ar: the
+// first member, "/" with 32 bit offsets or "/SYM64/" with 64 bit ones,
+// holds the number of symbols, their offsets, then their names each ended
+// by a NUL. Other archives are taken to have no symbols.
+#[cfg(feature = "static")]
+fn archive_symbols(bytes: &[u8]) -> Vec<String> {
+    const MAGIC: &[u8] = b"!<arch>\n";
+    const HEADER: usize = 60;
+
+    let Some(header) = bytes.strip_prefix(MAGIC).and_then(|rest| rest.get(..HEADER)) else {
+        return Vec::new();
+    };
+    let word = match String::from_utf8_lossy(&header[..16]).trim_end() {
+        "/" => 4,
+        "/SYM64/" => 8,
+        _ => return Vec::new(),
+    };
+    let size = String::from_utf8_lossy(&header[48..58]).trim().parse::<usize>().unwrap_or(0);
+    let start = MAGIC.len() + HEADER;
+    let Some(table) = bytes.get(start..start.saturating_add(size)) else {
+        return Vec::new();
+    };
+    let Some(count) = table.get(..word).map(|count| count.iter().fold(0usize, |n, &b| n << 8 | b as usize)) else {
+        return Vec::new();
+    };
+    let names = count.saturating_add(1).saturating_mul(word);
+    table
+        .get(names..)
+        .unwrap_or_default()
+        .split(|&b| b == 0)
+        .filter_map(|name| std::str::from_utf8(name).ok())
+        .map(str::to_owned)
+        .collect()
+}
+
+// Build the cgo wrapper of cess_pois into OUT_DIR
+#[cfg(feature = "build-go")]
+fn go_library(manifest_dir: &Path) -> PathBuf {
+    let out = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR not set")).join("libpois.so");
+    go_build(manifest_dir, "c-shared", &out);
+    out
+}
+
+// Its sources are looked up in CESS_POIS_GO_DIR, or in cgo/ next to main.h.
+// The go binary can be overridden with GO.
+#[cfg(feature = "build-go")]
+fn go_build(manifest_dir: &Path, mode: &str, out: &Path) {
+    use std::process::Command;
+
+    println!("cargo:rerun-if-env-changed=CESS_POIS_GO_DIR");
+    println!("cargo:rerun-if-env-changed=GO");
+    let src = env::var_os("CESS_POIS_GO_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| manifest_dir.join("cgo"));
+    if !src.join("go.mod").exists() {
+        panic!(
+            "build-go: no go.mod in {}, the cgo wrapper of cess_pois is not vendored in this repository, \
+             as cgo/main.so was built from an uncommitted cess_pois tree with no revision to pin: \
+             check out https://github.com/CESSProject/cess_pois and set CESS_POIS_GO_DIR to it",
+            src.display()
+        );
+    }
+    println!("cargo:rerun-if-changed={}", src.display());
+
+    let go = env::var_os("GO").unwrap_or_else(|| "go".into());
+    // -trimpath keeps the build machine's paths out of the library
+    let status = Command::new(&go)
+        .current_dir(&src)
+        .arg("build")
+        .arg(format!("-buildmode={}", mode))
+        .args(["-trimpath", "-o"])
+        .arg(out)
+        .arg(".")
+        .env("CGO_ENABLED", "1")
+        .status()
+        .unwrap_or_else(|e| panic!("build-go: failed to run {:?}: {}", go, e));
+    if !status.success() {
+        panic!("build-go: go build failed with {}", status);
+    }
+}
+
+// Generate the raw bindings of sys from cgo/main.h and the vendored
+// cgo/verifier.h it includes, as "cgo/verifier.h". main.h also includes
+// <gmp.h>, so the GMP headers must be installed.
+#[cfg(feature = "bindgen")]
+fn bindings(manifest_dir: &Path) {
+    println!("cargo:rerun-if-changed=cgo/main.h");
+    println!("cargo:rerun-if-changed=cgo/verifier.h");
+
+    let out = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR not set")).join("bindings.rs");
+    bindgen::Builder::default()
+        .header(manifest_dir.join("cgo/main.h").to_string_lossy())
+        .clang_arg(format!("-I{}", manifest_dir.display()))
+        // Only the exports, and the types they take
+        .allowlist_function(
+            "GenerateCommitChallenge|VerifyCommitAndAccProofs|GetByteArray.*|ReturnAnArray|FreeArray|\
+             ReturnArrayofArrays|GetOuterArrOfArrWithInner",
+        )
+        .layout_tests(false)
+        .generate()
+        .expect("bindgen: failed to generate bindings of cgo/main.h")
+        .write_to_file(&out)
+        .unwrap_or_else(|e| panic!("bindgen: failed to write {}: {}", out.display(), e));
+
+    // The checked in copy, used without the feature, must be the same
+    // declarations, whatever the comments and formatting
+    let declarations = |path: &Path| -> Vec<String> {
+        std::fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with("//") && !line.starts_with("/*"))
+            .map(String::from)
+            .collect()
+    };
+    let checked_in = manifest_dir.join("src/sys/bindings.rs");
+    if declarations(&out) != declarations(&checked_in) {
+        println!(
+            "cargo:warning=bindgen: {} differs from the bindings generated from cgo/main.h, replace it with {}",
+            checked_in.display(),
+            out.display()
+        );
+    }
+}
+
+// Generate the gRPC messages and service from proto/pois.proto,
+// with a vendored protoc so none has to be installed.
+#[cfg(feature = "grpc")]
+fn grpc() {
+    let protoc = protoc_bin_vendored::protoc_bin_path().expect("no vendored protoc for this platform");
+    std::env::set_var("PROTOC", protoc);
+    tonic_build::compile_protos("proto/pois.proto").expect("failed to compile proto/pois.proto");
+}
@@ -0,0 +1,80 @@
+/*
+ * Types of the cess_pois cgo wrapper, included by main.go and so by main.h.
+ *
+ * Recovered from the DWARF debug info of the prebuilt main.so, built from
+ * /home/thgy/work/cess_pois/cgo/verifier.h: every type main.so uses, with
+ * the field names, types and order it was compiled with. Types the wrapper
+ * declares but never uses are not in the debug info and are left out.
+ * main.so reports cess_pois as (devel), so there is no revision to pin.
+ */
+
+#ifndef CESS_POIS_CGO_VERIFIER_H
+#define CESS_POIS_CGO_VERIFIER_H
+
+#include <stdint.h>
+
+typedef int32_t NodeType;
+
+typedef struct {
+    int64_t fileIndex;
+    // [][]byte
+    unsigned char **roots;
+    // Number of roots
+    int rootsLength;
+    // Length of each root
+    int *subRootsLengths;
+} CommitC;
+
+typedef struct {
+    NodeType index;
+    unsigned char *label;
+    int labelLength;
+    // [][]byte
+    unsigned char **paths;
+    int *subPathsLengths;
+    int pathLength;
+    unsigned char *locs;
+    int locsLength;
+} MhtProofC;
+
+typedef struct {
+    MhtProofC *node;
+    // []*MhtProof
+    MhtProofC **parents;
+    int parentsLength;
+} CommitProofC;
+
+typedef struct {
+    unsigned char *b;
+    int length;
+} MyByte;
+
+typedef struct {
+    char *keyN;
+    char *keyG;
+    int64_t kC;
+    int64_t nC;
+    int64_t dC;
+} CommonParam;
+
+typedef struct {
+    char *id;
+    int length;
+} ProverID;
+
+// [][]int64, e.g. a challenge
+typedef struct {
+    int64_t **mainArray;
+    int *sub_array_lengths;
+    int length;
+} I64ArrOfArr;
+
+typedef struct {
+    int num;
+} Inner;
+
+typedef struct {
+    Inner *i;
+} Outer;
+
+#endif
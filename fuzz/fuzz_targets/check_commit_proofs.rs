@@ -0,0 +1,66 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+use cess_pois_rust_ffi::{
+    types::{Challenge, CommitChallenge, CommitProof, MhtProof},
+    validate::check_commit_proofs,
+};
+
+#[derive(Debug, Arbitrary)]
+struct Mht {
+    index: i32,
+    label: Vec<u8>,
+    paths: Vec<Vec<u8>>,
+    locs: Vec<u8>,
+}
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    // file_index, layer_selector, node_indices
+    challenge: Vec<(i64, i64, Vec<i64>)>,
+    proofs: Vec<Vec<(Option<Mht>, Vec<Mht>)>>,
+    n: i64,
+    d: i64,
+}
+
+impl From<Mht> for MhtProof {
+    fn from(mht: Mht) -> Self {
+        MhtProof {
+            index: mht.index,
+            label: mht.label,
+            paths: mht.paths,
+            locs: mht.locs,
+        }
+    }
+}
+
+// The structural checks, with any challenge and parameters
+fuzz_target!(|input: Input| {
+    let challenge = Challenge(
+        input
+            .challenge
+            .into_iter()
+            .map(|(file_index, layer_selector, node_indices)| CommitChallenge {
+                file_index,
+                layer_selector,
+                node_indices,
+            })
+            .collect(),
+    );
+    let proofs: Vec<Vec<CommitProof>> = input
+        .proofs
+        .into_iter()
+        .map(|file| {
+            file.into_iter()
+                .map(|(node, parents)| CommitProof {
+                    node: node.map(Into::into),
+                    parents: parents.into_iter().map(Into::into).collect(),
+                })
+                .collect()
+        })
+        .collect();
+
+    let _ = check_commit_proofs(&proofs, &challenge, input.n, input.d);
+});
@@ -0,0 +1,35 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use prost::Message;
+
+use cess_pois_rust_ffi::{
+    grpc::proto, merkle::check_path_shape, types::CommitProof,
+    utils::rust_commit_proof_array_to_commit_proof_c_array,
+};
+
+// Commit proofs as a miner submits them, over JSON-RPC or gRPC, then
+// through the checks and the marshalling to C they get before Go sees them.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(proofs) = serde_json::from_slice::<Vec<Vec<CommitProof>>>(data) {
+        check(proofs);
+    }
+    if let Ok(request) = proto::SubmitCommitProofsRequest::decode(data) {
+        let proofs = request
+            .files
+            .into_iter()
+            .map(|file| file.proofs.into_iter().map(CommitProof::from).collect())
+            .collect();
+        check(proofs);
+    }
+});
+
+fn check(proofs: Vec<Vec<CommitProof>>) {
+    for proof in proofs.iter().flatten() {
+        for mht in proof.node.iter().chain(&proof.parents) {
+            let _ = check_path_shape(mht);
+        }
+    }
+    let proofs_c = rust_commit_proof_array_to_commit_proof_c_array(&proofs);
+    assert_eq!(proofs_c.view().to_vec(), proofs);
+}
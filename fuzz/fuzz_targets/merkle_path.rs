@@ -0,0 +1,32 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+use cess_pois_rust_ffi::{
+    merkle::{path_root, verify_path},
+    types::MhtProof,
+};
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    root: Vec<u8>,
+    label: Vec<u8>,
+    paths: Vec<Vec<u8>>,
+    locs: Vec<u8>,
+}
+
+// Verification of any path against any root. A path that leads somewhere
+// must verify against the root it leads to.
+fuzz_target!(|input: Input| {
+    let proof = MhtProof {
+        index: 0,
+        label: input.label,
+        paths: input.paths,
+        locs: input.locs,
+    };
+    let _ = verify_path(&input.root, &proof);
+    if let Ok(root) = path_root(&proof) {
+        assert_eq!(verify_path(&root, &proof), Ok(()));
+    }
+});
@@ -0,0 +1,195 @@
+use std::{
+    ffi::CString,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tokio::{sync::Semaphore, task};
+
+use crate::{
+    backend::PoisBackend,
+    c_types::CommonParam,
+    ffi::{panic_message, CallError, PoisLibrary, VerifyError},
+    params::PoisParams,
+    types::{Challenge, Commit, CommitProof, ProverId, RsaKey},
+    utils::init_common_params,
+};
+
+// Async facade over PoisLibrary for tokio services.
+//
+// Calls run on tokio's blocking pool, at most max_blocking at a time.
+// A cgo call can not be interrupted, so when a timeout fires the future
+// resolves with Timeout right away: a call still waiting for its turn is
+// skipped, one already inside Go runs to completion and its result is
+// dropped. It keeps its slot until then, so the bound always holds.
+// Dropping the future cancels the call the same way.
+//
+// with_backend runs the calls on any PoisBackend, e.g. a MockBackend.
+
+#[derive(Debug)]
+pub enum AsyncCallError {
+    Timeout,
+    Rejected(VerifyError),
+    // Generating a challenge failed
+    Call(CallError),
+    // The blocking call panicked, with the panic message
+    Panicked(String),
+}
+
+impl fmt::Display for AsyncCallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AsyncCallError::Timeout => write!(f, "FFI call timed out"),
+            AsyncCallError::Rejected(e) => write!(f, "{}", e),
+            AsyncCallError::Call(e) => write!(f, "{}", e),
+            AsyncCallError::Panicked(message) => write!(f, "FFI call panicked: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for AsyncCallError {}
+
+// A CommonParam whose key strings are owned by it, as built by
+// init_common_params, so it can be shared with the blocking pool.
+pub struct OwnedCommonParam(CommonParam);
+
+// The key strings are never written, Go only reads them.
+unsafe impl Send for OwnedCommonParam {}
+unsafe impl Sync for OwnedCommonParam {}
+
+// Built by init_common_params, which leaks the key strings for it
+impl Drop for OwnedCommonParam {
+    fn drop(&mut self) {
+        unsafe {
+            drop(CString::from_raw(self.0.key_n));
+            drop(CString::from_raw(self.0.key_g));
+        }
+    }
+}
+
+impl OwnedCommonParam {
+    pub fn new(rsa_key: RsaKey, k: i64, n: i64, d: i64) -> Self {
+        OwnedCommonParam(init_common_params(rsa_key, k, n, d))
+    }
+
+    pub fn from_params(params: &PoisParams, rsa_key: RsaKey) -> Self {
+        Self::new(rsa_key, params.k, params.n, params.d)
+    }
+
+    // Go takes a *mut CommonParam, hand it a copy so it never aliases ours
+    pub(crate) fn to_c(&self) -> CommonParam {
+        CommonParam {
+            key_n: self.0.key_n,
+            key_g: self.0.key_g,
+            k: self.0.k,
+            n: self.0.n,
+            d: self.0.d,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AsyncPoisLibrary {
+    lib: Arc<dyn PoisBackend>,
+    permits: Arc<Semaphore>,
+    timeout: Option<Duration>,
+}
+
+impl AsyncPoisLibrary {
+    pub fn new(lib: PoisLibrary, max_blocking: usize) -> Self {
+        Self::with_backend(Arc::new(lib), max_blocking)
+    }
+
+    pub fn with_backend(lib: Arc<dyn PoisBackend>, max_blocking: usize) -> Self {
+        AsyncPoisLibrary {
+            lib,
+            permits: Arc::new(Semaphore::new(max_blocking)),
+            timeout: None,
+        }
+    }
+
+    // Timeout for every call, including the time spent waiting for a slot
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub async fn generate_commit_challenge(
+        &self,
+        commits: Vec<Commit>,
+        common_params: Arc<OwnedCommonParam>,
+        id: ProverId,
+    ) -> Result<Challenge, AsyncCallError> {
+        self.run(move |lib| {
+            lib.try_generate_commit_challenge(&commits, &mut common_params.to_c(), &id)
+        })
+        .await?
+        .map_err(AsyncCallError::Call)
+    }
+
+    pub async fn verify_commit_and_acc_proofs(
+        &self,
+        commit_proof: Vec<Vec<CommitProof>>,
+        challenge: Challenge,
+        common_params: Arc<OwnedCommonParam>,
+        id: ProverId,
+    ) -> Result<(), AsyncCallError> {
+        self.run(move |lib| {
+            lib.verify_commit_and_acc_proofs(commit_proof, challenge, &mut common_params.to_c(), &id)
+        })
+        .await?
+        .map_err(AsyncCallError::Rejected)
+    }
+
+    async fn run<T, F>(&self, call: F) -> Result<T, AsyncCallError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn PoisBackend) -> T + Send + 'static,
+    {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        // Set once this future returns or is dropped
+        let _cancel = CancelOnDrop(cancelled.clone());
+        let call = {
+            let (lib, permits, cancelled) = (self.lib.clone(), self.permits.clone(), cancelled.clone());
+            async move {
+                let permit = permits.acquire_owned().await.expect("semaphore is never closed");
+                task::spawn_blocking(move || {
+                    let _permit = permit;
+                    if cancelled.load(Ordering::Acquire) {
+                        return None;
+                    }
+                    Some(call(lib.as_ref()))
+                })
+                .await
+            }
+        };
+
+        let result = match self.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, call).await {
+                Ok(result) => result,
+                Err(_) => return Err(AsyncCallError::Timeout),
+            },
+            None => call.await,
+        };
+
+        match result {
+            Ok(Some(value)) => Ok(value),
+            // Only skipped after a timeout, which returned above
+            Ok(None) => Err(AsyncCallError::Timeout),
+            Err(e) if e.is_panic() => Err(AsyncCallError::Panicked(panic_message(e.into_panic()))),
+            Err(e) => Err(AsyncCallError::Panicked(e.to_string())),
+        }
+    }
+}
+
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Release);
+    }
+}
@@ -0,0 +1,159 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use crate::{
+    c_types::CommonParam,
+    ffi::{CallError, PoisLibrary, VerifyError},
+    types::{Challenge, Commit, CommitProof, ProverId},
+    utils::lock,
+};
+
+// The calls into the verifier, behind a trait so that what drives them,
+// VerifierService, AsyncPoisLibrary or code outside this crate, can be
+// tested without the Go library.
+//
+// PoisLibrary is the real backend. MockBackend answers with challenges
+// and verdicts scripted in advance and records every call it gets.
+pub trait PoisBackend: Send + Sync {
+    // See PoisLibrary::try_generate_commit_challenge
+    fn try_generate_commit_challenge(
+        &self,
+        commits: &[Commit],
+        common_params: &mut CommonParam,
+        id: &ProverId,
+    ) -> Result<Challenge, CallError>;
+
+    // See PoisLibrary::verify_commit_and_acc_proofs
+    fn verify_commit_and_acc_proofs(
+        &self,
+        commit_proof: Vec<Vec<CommitProof>>,
+        challenge: Challenge,
+        common_params: &mut CommonParam,
+        id: &ProverId,
+    ) -> Result<(), VerifyError>;
+
+    // Whether verify_commit_and_acc_proofs can accept proofs at all, see
+    // PoisLibrary::can_verify
+    fn can_verify(&self) -> bool {
+        true
+    }
+}
+
+impl PoisBackend for PoisLibrary {
+    fn try_generate_commit_challenge(
+        &self,
+        commits: &[Commit],
+        common_params: &mut CommonParam,
+        id: &ProverId,
+    ) -> Result<Challenge, CallError> {
+        PoisLibrary::try_generate_commit_challenge(self, commits, common_params, id)
+    }
+
+    fn verify_commit_and_acc_proofs(
+        &self,
+        commit_proof: Vec<Vec<CommitProof>>,
+        challenge: Challenge,
+        common_params: &mut CommonParam,
+        id: &ProverId,
+    ) -> Result<(), VerifyError> {
+        PoisLibrary::verify_commit_and_acc_proofs(self, commit_proof, challenge, common_params, id)
+    }
+
+    fn can_verify(&self) -> bool {
+        PoisLibrary::can_verify(self)
+    }
+}
+
+// A call MockBackend received, with its arguments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockCall {
+    GenerateCommitChallenge {
+        id: ProverId,
+        commits: Vec<Commit>,
+    },
+    VerifyCommitAndAccProofs {
+        id: ProverId,
+        commit_proof: Vec<Vec<CommitProof>>,
+        challenge: Challenge,
+    },
+}
+
+// Answers each call with the next scripted result, in the order they were
+// pushed. Once they run out challenges are empty, which is how Go rejects
+// commits, and proofs are accepted. Nothing is checked, not even the shape
+// of the proofs, the verdict is whatever was scripted.
+#[derive(Default)]
+pub struct MockBackend {
+    challenges: Mutex<VecDeque<Result<Challenge, CallError>>>,
+    verdicts: Mutex<VecDeque<Result<(), VerifyError>>>,
+    calls: Mutex<Vec<MockCall>>,
+    no_verdict: AtomicBool,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push_challenge(&self, challenge: Result<Challenge, CallError>) {
+        lock(&self.challenges).push_back(challenge);
+    }
+
+    pub fn push_verdict(&self, verdict: Result<(), VerifyError>) {
+        lock(&self.verdicts).push_back(verdict);
+    }
+
+    // Act as a library that reports no verdict, as the prebuilt one
+    pub fn set_can_verify(&self, can_verify: bool) {
+        self.no_verdict.store(!can_verify, Ordering::Relaxed);
+    }
+
+    // Every call so far, oldest first
+    pub fn calls(&self) -> Vec<MockCall> {
+        lock(&self.calls).clone()
+    }
+
+    // Scripted results not used yet, challenges and verdicts
+    pub fn remaining(&self) -> (usize, usize) {
+        (lock(&self.challenges).len(), lock(&self.verdicts).len())
+    }
+}
+
+impl PoisBackend for MockBackend {
+    fn try_generate_commit_challenge(
+        &self,
+        commits: &[Commit],
+        _common_params: &mut CommonParam,
+        id: &ProverId,
+    ) -> Result<Challenge, CallError> {
+        lock(&self.calls).push(MockCall::GenerateCommitChallenge {
+            id: id.clone(),
+            commits: commits.to_vec(),
+        });
+        lock(&self.challenges).pop_front().unwrap_or_else(|| Ok(Challenge::default()))
+    }
+
+    fn verify_commit_and_acc_proofs(
+        &self,
+        commit_proof: Vec<Vec<CommitProof>>,
+        challenge: Challenge,
+        _common_params: &mut CommonParam,
+        id: &ProverId,
+    ) -> Result<(), VerifyError> {
+        lock(&self.calls).push(MockCall::VerifyCommitAndAccProofs {
+            id: id.clone(),
+            commit_proof,
+            challenge,
+        });
+        lock(&self.verdicts).pop_front().unwrap_or(Ok(()))
+    }
+
+    fn can_verify(&self) -> bool {
+        !self.no_verdict.load(Ordering::Relaxed)
+    }
+}
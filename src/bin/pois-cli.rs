@@ -0,0 +1,235 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Args, Parser, Subcommand};
+use serde::de::DeserializeOwned;
+
+use cess_pois_rust_ffi::{
+    ffi::PoisLibrary,
+    loader::VersionPolicy,
+    params::PoisParams,
+    types::{Challenge, Commit, CommitProof, MhtProof, RsaKey},
+    utils::{init_common_params, rsa_keygen},
+};
+
+// Debugging tool for operators. Keys, commits, challenges and proofs are
+// read and written as JSON, the same serde encoding as the library types.
+// A challenge is the flat [file_index, layer_selector, s_1 .. s_k] rows.
+
+#[derive(Parser)]
+#[command(name = "pois-cli", version, about = "CESS proof of idle space tools")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate an RSA accumulator key
+    Keygen {
+        #[command(flatten)]
+        params: ParamsArgs,
+        /// Write the key here instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Print the resolved parameters as TOML
+    Params {
+        #[command(flatten)]
+        params: ParamsArgs,
+    },
+    /// Generate the commit challenge for a miner's commits
+    Challenge {
+        #[command(flatten)]
+        verifier: VerifierArgs,
+        #[arg(long)]
+        commits: PathBuf,
+        /// Write the challenge here instead of stdout, which Go also logs to
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Verify a miner's commit proofs against its challenge. Needs a Go library reporting verdicts:
+    /// with the prebuilt cgo/main.so, which does not, it always fails
+    Verify {
+        #[command(flatten)]
+        verifier: VerifierArgs,
+        #[arg(long)]
+        proofs: PathBuf,
+        #[arg(long)]
+        challenge: PathBuf,
+    },
+    /// Summarize a commit proofs file
+    Inspect { proofs: PathBuf },
+}
+
+#[derive(Args)]
+struct ParamsArgs {
+    #[arg(long, default_value = "devnet")]
+    preset: String,
+    /// TOML params file, overrides --preset
+    #[arg(long)]
+    config: Option<PathBuf>,
+}
+
+impl ParamsArgs {
+    fn load(&self) -> Result<PoisParams, String> {
+        match &self.config {
+            Some(path) => PoisParams::from_toml_file(path),
+            None => PoisParams::preset(&self.preset),
+        }
+        .map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Args)]
+struct VerifierArgs {
+    #[command(flatten)]
+    params: ParamsArgs,
+    /// Key file written by keygen
+    #[arg(long)]
+    key: PathBuf,
+    /// Prover/miner ID
+    #[arg(long)]
+    id: String,
+    /// Go library, looked up in CESS_POIS_LIB, next to the executable and in the system paths if not given
+    #[arg(long)]
+    lib: Option<PathBuf>,
+    /// Refuse a Go library that does not report its version, the prebuilt one among them
+    #[arg(long)]
+    strict_version: bool,
+}
+
+impl VerifierArgs {
+    fn library(&self) -> Result<PoisLibrary, String> {
+        let policy = if self.strict_version { VersionPolicy::Strict } else { VersionPolicy::Lenient };
+        let lib = match &self.lib {
+            Some(path) => PoisLibrary::open_with(path, policy),
+            None => PoisLibrary::discover_with(policy),
+        }
+        .map_err(|e| e.to_string())?;
+        // There is no logger here to show the one of check_version
+        if lib.version().is_none() {
+            eprintln!("warning: the Go library does not export PoisVersion, it may not match these bindings (see --strict-version)");
+        }
+        Ok(lib)
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse().command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(command: Command) -> Result<(), String> {
+    match command {
+        Command::Keygen { params, out } => {
+            let key = rsa_keygen(params.load()?.key_bits);
+            output(out.as_deref(), &to_json(&key)?)
+        }
+        Command::Params { params } => {
+            print!("{}", toml::to_string(&params.load()?).map_err(|e| e.to_string())?);
+            Ok(())
+        }
+        Command::Challenge { verifier, commits, out } => {
+            let commits: Vec<Commit> = read_json(&commits)?;
+            let params = verifier.params.load()?;
+            let key: RsaKey = read_json(&verifier.key)?;
+            let mut common_params = init_common_params(key, params.k, params.n, params.d);
+
+            let challenge = verifier
+                .library()?
+                .try_generate_commit_challenge(&commits, &mut common_params, &verifier.id.as_str().into())
+                .map_err(|e| e.to_string())?;
+            output(out.as_deref(), &to_json(&challenge.to_flat())?)
+        }
+        Command::Verify {
+            verifier,
+            proofs,
+            challenge,
+        } => {
+            let proofs: Vec<Vec<CommitProof>> = read_json(&proofs)?;
+            let challenge: Vec<Vec<i64>> = read_json(&challenge)?;
+            let challenge = Challenge::try_from(challenge).map_err(|e| e.to_string())?;
+            let params = verifier.params.load()?;
+            let key: RsaKey = read_json(&verifier.key)?;
+            let mut common_params = init_common_params(key, params.k, params.n, params.d);
+
+            // Without a verdict from Go there is nothing to report but the
+            // shape of the proofs, which is not a verification
+            let lib = verifier.library()?;
+            if !lib.can_verify() {
+                return Err("commit proof verification is not wired: the Go library does not export \
+                            PoisLastError, so it reports no verdict"
+                    .to_string());
+            }
+            lib.verify_commit_and_acc_proofs(proofs, challenge, &mut common_params, &verifier.id.into())
+                .map_err(|e| e.to_string())?;
+            println!("ok");
+            Ok(())
+        }
+        Command::Inspect { proofs } => {
+            let proofs: Vec<Vec<CommitProof>> = read_json(&proofs)?;
+            for (file, file_proofs) in proofs.iter().enumerate() {
+                println!("proofs {} ({} nodes):", file, file_proofs.len());
+                for (position, proof) in file_proofs.iter().enumerate() {
+                    match &proof.node {
+                        Some(node) => println!("  [{}] {}", position, describe(node)),
+                        None => println!("  [{}] no node", position),
+                    }
+                    for (i, parent) in proof.parents.iter().enumerate() {
+                        println!("      parent {:>3} {}", i, describe(parent));
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+fn describe(proof: &MhtProof) -> String {
+    format!(
+        "node {} label {} ({} bytes), {} path nodes, {} locs",
+        proof.index,
+        hex_prefix(&proof.label),
+        proof.label.len(),
+        proof.paths.len(),
+        proof.locs.len()
+    )
+}
+
+// The first bytes of a label are enough to tell nodes apart
+fn hex_prefix(bytes: &[u8]) -> String {
+    let hex: String = bytes.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+    if bytes.len() > 8 {
+        hex + ".."
+    } else {
+        hex
+    }
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let s = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    serde_json::from_str(&s).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn output(path: Option<&Path>, s: &str) -> Result<(), String> {
+    match path {
+        Some(path) => fs::write(path, format!("{}\n", s)).map_err(|e| format!("{}: {}", path.display(), e)),
+        None => {
+            println!("{}", s);
+            Ok(())
+        }
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string_pretty(value).map_err(|e| e.to_string())
+}
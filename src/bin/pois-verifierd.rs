@@ -0,0 +1,123 @@
+use std::{fs, net::SocketAddr, path::PathBuf, process::ExitCode, sync::Arc, time::Duration};
+
+use clap::Parser;
+
+use cess_pois_rust_ffi::{
+    async_ffi::OwnedCommonParam,
+    ffi::PoisLibrary,
+    loader::VersionPolicy,
+    params::PoisParams,
+    rpc,
+    service::VerifierService,
+    types::RsaKey,
+};
+
+// Verifier daemon serving the JSON-RPC API of cess_pois_rust_ffi::rpc.
+// It listens on localhost unless told otherwise, there is no
+// authentication so put it behind a proxy before exposing it.
+
+#[derive(Parser)]
+#[command(
+    name = "pois-verifierd",
+    version,
+    about = "CESS proof of idle space verifier daemon",
+    long_about = "CESS proof of idle space verifier daemon\n\n\
+                  Miners can register, submit commits and get their challenge. Their proofs are not verified: \
+                  space and deletion proofs are unsupported, answered as such, and commit proofs are too with a \
+                  Go library reporting no verdict, the prebuilt cgo/main.so among them."
+)]
+struct Args {
+    #[arg(long, default_value = "127.0.0.1:9944")]
+    listen: SocketAddr,
+    /// Key file written by pois-cli keygen
+    #[arg(long)]
+    key: PathBuf,
+    #[arg(long, default_value = "devnet")]
+    preset: String,
+    /// TOML params file, overrides --preset
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Go library, looked up in CESS_POIS_LIB, next to the executable and in the system paths if not given
+    #[arg(long)]
+    lib: Option<PathBuf>,
+    /// Refuse a Go library that does not report its version, the prebuilt one among them
+    #[arg(long)]
+    strict_version: bool,
+    /// Seconds a miner has to answer its challenge
+    #[arg(long, default_value_t = 60)]
+    challenge_timeout: u64,
+    /// Also serve the gRPC API on this address
+    #[cfg(feature = "grpc")]
+    #[arg(long)]
+    grpc_listen: Option<SocketAddr>,
+    /// Keep miner state in this database across restarts
+    #[cfg(feature = "sled")]
+    #[arg(long)]
+    db: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Args::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(args: Args) -> Result<(), String> {
+    let params = match &args.config {
+        Some(path) => PoisParams::from_toml_file(path),
+        None => PoisParams::preset(&args.preset),
+    }
+    .map_err(|e| e.to_string())?;
+    let key = fs::read_to_string(&args.key).map_err(|e| format!("{}: {}", args.key.display(), e))?;
+    let key: RsaKey = serde_json::from_str(&key).map_err(|e| format!("{}: {}", args.key.display(), e))?;
+
+    let policy = if args.strict_version { VersionPolicy::Strict } else { VersionPolicy::Lenient };
+    let lib = match &args.lib {
+        Some(path) => PoisLibrary::open_with(path, policy),
+        None => PoisLibrary::discover_with(policy),
+    }
+    .map_err(|e| e.to_string())?;
+    // There is no logger here to show the one of check_version
+    if lib.version().is_none() {
+        eprintln!("warning: the Go library does not export PoisVersion, it may not match these bindings (see --strict-version)");
+    }
+    if !lib.can_verify() {
+        eprintln!("warning: the Go library reports no verdict on commit proofs, submitting them is unsupported");
+    }
+    let service = VerifierService::new(
+        lib,
+        OwnedCommonParam::from_params(&params, key),
+    )
+    .with_challenge_timeout(Duration::from_secs(args.challenge_timeout));
+
+    #[cfg(feature = "sled")]
+    let service = match &args.db {
+        Some(path) => {
+            let store = cess_pois_rust_ffi::store::SledStore::open(path).map_err(|e| e.to_string())?;
+            service.with_store(Arc::new(store)).map_err(|e| e.to_string())?
+        }
+        None => service,
+    };
+
+    let service = Arc::new(service);
+    #[cfg(feature = "grpc")]
+    if let Some(addr) = args.grpc_listen {
+        let server = tonic::transport::Server::builder().add_service(cess_pois_rust_ffi::grpc::server(service.clone()));
+        tokio::spawn(async move {
+            if let Err(e) = server.serve(addr).await {
+                eprintln!("error: gRPC server: {}", e);
+            }
+        });
+        eprintln!("pois-verifierd serving gRPC on {}", addr);
+    }
+
+    let (addr, handle) = rpc::serve(service, args.listen).await.map_err(|e| e.to_string())?;
+    eprintln!("pois-verifierd listening on {}", addr);
+    handle.stopped().await;
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use std::os::raw::{
-    c_int, c_long, c_char, c_uchar, 
+    c_int, c_char, c_uchar, 
 };
 
 pub type NodeType = c_int;
@@ -61,6 +61,7 @@ pub struct MhtProofC {
     pub label: *mut c_uchar,
     pub label_length: c_int,
 
+    // paths [][]byte
     pub paths: *mut *mut c_uchar,
     pub sub_paths_lengths: *mut c_int,
     pub path_length: c_int,
@@ -84,7 +85,18 @@ pub struct CommitProofC {
 #[repr(C)]
 pub struct MyByte {
     pub b: *mut u8,
-    pub length: c_long,
+    pub length: c_int,
+}
+
+// GetOuterArrOfArrWithInner
+#[repr(C)]
+pub struct Inner {
+    pub num: c_int,
+}
+
+#[repr(C)]
+pub struct Outer {
+    pub i: *mut Inner,
 }
 
 #[repr(C)]
@@ -102,20 +114,59 @@ pub struct ProverID {
     pub length: c_int, // Length of ID []byte
 }
 
+// [][]T as pointer to the sub arrays,
+// the length of each sub array and the number of sub arrays.
+// Built with ArrOfArrBuf and read with ArrOfArrView.
+#[derive(Debug)]
+#[repr(C)]
+pub struct ArrOfArr<T> {
+    pub main_array: *mut *mut T,
+    pub sub_array_lengths: *mut c_int,
+    pub length: c_int,
+}
+
 // Some types that can be represented by [][]int64 are
 // Challenge
-// [][]int64
+pub type I64ArrOfArr = ArrOfArr<i64>;
+
+pub type I32ArrOfArr = ArrOfArr<i32>;
+
+// Go functions with multiple return values are exported
+// by cgo as a struct of r0, r1, ... fields.
+// Tuples have no defined layout, so mirror those structs here.
+
+// struct GenerateCommitChallenge_return
+#[repr(C)]
+pub struct GenerateCommitChallengeReturn {
+    pub r0: *mut *mut i64,
+    pub r1: *mut i32,
+    pub r2: i32,
+}
+
+// struct ReturnAnArray_return
 #[repr(C)]
-pub struct I64ArrOfArr {
-    pub main_array: *mut *mut i64,
-    pub sub_array_lengths: *mut i32,
-    pub length: i32,
+pub struct ReturnAnArrayReturn {
+    pub r0: *mut c_int,
+    pub r1: *mut c_int,
 }
 
-// i32ArrOfArr
+// struct ReturnArrayofArrays_return
 #[repr(C)]
-pub struct I32ArrOfArr {
-    pub main_array: *mut *mut i32,
-    pub sub_array_length: *mut i32,
-    pub length: i32,
-}
\ No newline at end of file
+pub struct ReturnArrayofArraysReturn {
+    pub r0: *mut *mut c_int,
+    pub r1: *mut i32,
+    pub r2: i32,
+}
+// struct PoisVersion_return, see loader
+#[repr(C)]
+pub struct PoisVersionReturn {
+    pub r0: c_int,
+    pub r1: c_int,
+}
+
+// struct PoisLastError_return, see diagnostics
+#[repr(C)]
+pub struct PoisLastErrorReturn {
+    pub r0: c_int,
+    pub r1: *mut c_char,
+}
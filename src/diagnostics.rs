@@ -0,0 +1,124 @@
+use std::{
+    ffi::CStr,
+    fmt,
+    os::raw::{c_char, c_int},
+    slice,
+};
+
+use crate::{c_types::PoisLastErrorReturn, ffi::{go_lock, PoisLibrary}};
+
+// Logs and errors of the Go library.
+//
+// A panic in Go takes the whole process down, and the Go code prints what
+// went wrong to stdout. Libraries that export these instead recover panics
+// in every wrapper and keep the error for the caller to take, and send
+// their log lines to a callback:
+//
+//     typedef void (*PoisLogFunc)(int level, char* message, int length);
+//
+//     //export PoisSetLogger
+//     func PoisSetLogger(log C.PoisLogFunc)
+//     //export PoisLastError
+//     func PoisLastError() (C.int, *C.char) // code, C.CString message or nil
+//
+// PoisLastError returns 0 when the last call succeeded and clears the
+// error. The log level is the one of log/slog, so -4 debug, 0 info, 4 warn
+// and 8 error. The lines are forwarded to the log crate with the target
+// GO_LOG_TARGET.
+//
+// Both exports are optional, and no library has them: the Go wrapper
+// neither recovers panics nor forwards its logs, the prebuilt cgo/main.so
+// included. So in practice Go still prints to stdout and its panics still
+// abort, this is only the Rust side of the interface.
+
+pub const GO_LOG_TARGET: &str = "cess_pois::go";
+
+// Error codes of PoisLastError
+pub const GO_ERROR_INVALID_INPUT: c_int = 1;
+pub const GO_ERROR_PANIC: c_int = 2;
+
+pub(crate) type PoisLogFunc = unsafe extern "C" fn(c_int, *const c_char, c_int);
+pub(crate) type PoisSetLoggerFunc = unsafe extern "C" fn(PoisLogFunc);
+pub(crate) type PoisLastErrorFunc = unsafe extern "C" fn() -> PoisLastErrorReturn;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GoErrorKind {
+    // The arguments were rejected
+    InvalidInput,
+    // A panic recovered by the Go wrapper
+    Panic,
+    Other(i32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoError {
+    pub kind: GoErrorKind,
+    pub message: String,
+}
+
+impl GoError {
+    pub fn from_code(code: c_int, message: String) -> Self {
+        let kind = match code {
+            GO_ERROR_INVALID_INPUT => GoErrorKind::InvalidInput,
+            GO_ERROR_PANIC => GoErrorKind::Panic,
+            code => GoErrorKind::Other(code),
+        };
+        GoError { kind, message }
+    }
+}
+
+impl fmt::Display for GoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            GoErrorKind::InvalidInput => write!(f, "Go rejected the input: {}", self.message),
+            GoErrorKind::Panic => write!(f, "Go panicked: {}", self.message),
+            GoErrorKind::Other(code) => write!(f, "Go error {}: {}", code, self.message),
+        }
+    }
+}
+
+impl std::error::Error for GoError {}
+
+// The log crate level of a slog level
+pub fn go_log_level(level: c_int) -> log::Level {
+    match level {
+        i32::MIN..=-5 => log::Level::Trace,
+        -4..=-1 => log::Level::Debug,
+        0..=3 => log::Level::Info,
+        4..=7 => log::Level::Warn,
+        _ => log::Level::Error,
+    }
+}
+
+// Called by Go on whatever thread logs, it must not unwind
+pub(crate) unsafe extern "C" fn forward_log(level: c_int, message: *const c_char, length: c_int) {
+    let message = match usize::try_from(length) {
+        Ok(length) if !message.is_null() => slice::from_raw_parts(message as *const u8, length),
+        _ => &[],
+    };
+    log::log!(target: GO_LOG_TARGET, go_log_level(level), "{}", String::from_utf8_lossy(message).trim_end());
+}
+
+pub(crate) fn install_logger(lib: &PoisLibrary) {
+    if let Some(set_logger) = lib.exports().set_logger {
+        let _lock = go_lock();
+        unsafe { set_logger(forward_log) };
+    }
+}
+
+// The error of the call just made, the caller must still hold the Go lock
+pub(crate) fn take_error(lib: &PoisLibrary) -> Option<GoError> {
+    let last_error = lib.exports().last_error?;
+    let error = unsafe { last_error() };
+    let message = if error.r1.is_null() {
+        String::new()
+    } else {
+        // Allocated with C.CString
+        unsafe {
+            let message = CStr::from_ptr(error.r1).to_string_lossy().into_owned();
+            libc::free(error.r1 as *mut libc::c_void);
+            message
+        }
+    };
+    (error.r0 != 0).then(|| GoError::from_code(error.r0, message))
+}
@@ -1,10 +1,10 @@
 
-use crate::{c_types::MyByte, utils::c_ptr_to_i64_array_of_array};
-use std::os::raw::{c_char, c_int, c_long};
+use crate::{c_types::{MyByte, ReturnAnArrayReturn, ReturnArrayofArraysReturn}, views::ArrOfArrView};
+use std::os::raw::{c_int, c_long};
 use libloading::Symbol;
 
-use crate::utils::load_library; // Dummy Type for testing
-type GetByteArrayFunc = unsafe extern "C" fn(*mut c_char, c_long);
+use crate::utils::load_default_library; // Dummy Type for testing
+type GetByteArrayFunc = unsafe extern "C" fn(*mut u8, c_int);
 type GetByteArrayAsStructFunc = unsafe extern "C" fn(*mut MyByte);
 type GetByteArrayAsStructArrayFunc = unsafe extern "C" fn(*mut MyByte, c_long);
 
@@ -16,26 +16,26 @@ type GetByteArrayAsStructArrayFunc = unsafe extern "C" fn(*mut MyByte, c_long);
  */ 
 type GetByteArrayOfArrayFunc = unsafe extern "C" fn(*mut *mut u8, c_int, *mut c_int);
 
-type GetArrayFunc = extern "C" fn() -> (*mut c_int, *mut c_int);
+type GetArrayFunc = extern "C" fn() -> ReturnAnArrayReturn;
 type FreeArrayFunc = extern "C" fn(*mut c_int);
-type GetArrayOfArrayFunc = extern "C" fn() -> (*mut *mut i64, *mut i32, i32);
+type GetArrayOfArrayFunc = extern "C" fn() -> ReturnArrayofArraysReturn;
 
 pub fn call_get_byte_array() {
-    let lib = load_library("cgo/main.so");
+    let lib = load_default_library();
     unsafe {
         // Pass the byte array to the C function
         let get_byte_array: Symbol<GetByteArrayFunc> =
             lib.get(b"GetByteArray").expect("Failed to retrieve symbol");
 
         let data: [u8; 3] = [1, 2, 3];
-        let data_ptr = data.as_ptr() as *mut c_char;
-        let data_len = data.len() as c_long;
+        let data_ptr = data.as_ptr() as *mut u8;
+        let data_len = data.len() as c_int;
         get_byte_array(data_ptr, data_len);
     }
 }
 
 pub fn call_get_byte_array_as_struct() {
-    let lib = load_library("cgo/main.so");
+    let lib = load_default_library();
     unsafe {
         // Pass the byte array to the C function
         let get_byte_array_as_struct: Symbol<GetByteArrayAsStructFunc> = lib
@@ -45,7 +45,7 @@ pub fn call_get_byte_array_as_struct() {
         let data: [u8; 3] = [1, 2, 3];
         let mut my_byte = MyByte {
             b: data.as_ptr() as *mut u8,
-            length: data.len() as c_long,
+            length: data.len() as c_int,
         };
         // let data_ptr = data.as_ptr() as *mut c_char;
         // let data_len = data.len() as c_long;
@@ -54,7 +54,7 @@ pub fn call_get_byte_array_as_struct() {
 }
 
 pub fn call_get_byte_array_as_struct_array() {
-    let lib = load_library("cgo/main.so");
+    let lib = load_default_library();
     unsafe {
         // Pass the byte array to the C function
         let get_byte_array_by_struct: Symbol<GetByteArrayAsStructArrayFunc> = lib
@@ -64,12 +64,12 @@ pub fn call_get_byte_array_as_struct_array() {
         let data1: [u8; 3] = [1, 2, 3];
         let my_byte1 = MyByte {
             b: data1.as_ptr() as *mut u8,
-            length: data1.len() as c_long,
+            length: data1.len() as c_int,
         };
         let data2: [u8; 4] = [5, 6, 7, 8];
         let my_byte2 = MyByte {
             b: data2.as_ptr() as *mut u8,
-            length: data2.len() as c_long,
+            length: data2.len() as c_int,
         };
 
         let mut data = vec![my_byte1, my_byte2];
@@ -80,7 +80,7 @@ pub fn call_get_byte_array_as_struct_array() {
 }
 
 pub fn call_get_byte_array_of_array() {
-    let lib = load_library("cgo/main.so");
+    let lib = load_default_library();
     unsafe {
         let get_byte_array_of_array: Symbol<GetByteArrayOfArrayFunc> = lib
             .get(b"GetByteArrayOfArray")
@@ -108,7 +108,7 @@ pub fn call_get_byte_array_of_array() {
 
 pub fn call_return_an_array() {
     // Load the Go shared library
-    let lib = load_library("cgo/main.so");
+    let lib = load_default_library();
     unsafe {
         // Get the symbols for the functions
         let get_array: libloading::Symbol<GetArrayFunc> = lib
@@ -118,15 +118,20 @@ pub fn call_return_an_array() {
             lib.get(b"FreeArray").expect("Failed to retrieve symbol");
 
         // Call the Go function to get the arrays
-        let (arr1_ptr, arr2_ptr) = get_array();
+        let arrays = get_array();
+        let (arr1_ptr, arr2_ptr) = (arrays.r0, arrays.r1);
 
         // Convert the C arrays to Rust slices
         let arr1_slice: &[c_int] = std::slice::from_raw_parts(arr1_ptr, 4);
         let arr2_slice: &[c_int] = std::slice::from_raw_parts(arr2_ptr, 4);
 
         // Convert the slices to Vec<i32>
-        let arr1_vec: Vec<i32> = arr1_slice.iter().map(|&val| val as i32).collect();
-        let arr2_vec: Vec<i32> = arr2_slice.iter().map(|&val| val as i32).collect();
+        let arr1_vec: Vec<i32> = arr1_slice.to_vec();
+        let arr2_vec: Vec<i32> = arr2_slice.to_vec();
+
+        // The arrays are allocated by Go with C.malloc
+        free_array(arr1_ptr);
+        free_array(arr2_ptr);
 
         // Print the arrays
         println!("Array 1: {:?}", arr1_vec);
@@ -136,15 +141,18 @@ pub fn call_return_an_array() {
 
 pub fn call_return_array_of_array() {
     // Load the Go shared library
-    let lib = load_library("cgo/main.so");
+    let lib = load_default_library();
     unsafe {
         let get_array_of_array: libloading::Symbol<GetArrayOfArrayFunc> = lib
             .get(b"ReturnArrayofArrays")
             .expect("Failed to retrieve symbol");
 
-        let (c_arrays, c_lengths, main_array_length) = get_array_of_array();
+        let arrays = get_array_of_array();
 
-        let array = c_ptr_to_i64_array_of_array(c_arrays, c_lengths, main_array_length);
+        // Rows of C ints, not int64
+        let array = ArrOfArrView::from_raw(arrays.r0, arrays.r1, arrays.r2)
+            .expect("Go returned a malformed array")
+            .to_vec();
 
         println!("Array {:?}", array);
     }
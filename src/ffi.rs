@@ -1,11 +1,23 @@
-use libloading::Symbol;
+#[cfg(not(feature = "static"))]
+use libloading::Library;
 use crate::{
-    c_types::{CommitC, CommonParam, ProverID, CommitProofC, MhtProofC, I64ArrOfArr},
-    types::{Commit, CommitProof},
-    utils::{c_ptr_to_i64_array_of_array, load_library, rust_commit_array_to_commit_c_array, commit_c_array_to_rust_commit_array, i64_array_of_array_to_c_ptr},
+    c_types::{CommitC, CommonParam, ProverID, CommitProofC, I64ArrOfArr, GenerateCommitChallengeReturn},
+    diagnostics::{self, GoError, PoisLastErrorFunc, PoisSetLoggerFunc},
+    loader::PoisVersion,
+    telemetry::{self, Timer},
+    types::{Challenge, ChallengeError, Commit, CommitProof, ProverId},
+    utils::rust_commit_array_to_commit_c_array,
+    validate::{check_commit_proofs, ProofShapeError},
+    views::{ArrOfArrBuf, CommitProofCArrayGuard, I64ArrOfArrGuard, MarshalError},
 };
+use rayon::prelude::*;
 use std::{
-    os::raw::{c_char, c_int, c_long, c_uchar}, ptr,
+    any::Any,
+    fmt,
+    os::raw::{c_char, c_int},
+    panic::{self, AssertUnwindSafe},
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
 };
 
 type GenerateCommitChallengeFunc = unsafe extern "C" fn(
@@ -13,81 +25,536 @@ type GenerateCommitChallengeFunc = unsafe extern "C" fn(
     c_int, // Length of CommitC Array
     *mut CommonParam, // CommonParam
     *mut ProverID, // Prover/Miner ID
-) -> (*mut *mut i64, *mut i32, i32);
+) -> GenerateCommitChallengeReturn;
 
 type VerifyCommitAndAccProofsFunc = unsafe extern "C" fn(
-    // *mut *mut CommitProofC,
-    // c_int, 
+    *mut *mut CommitProofC,
+    c_int,
     *mut I64ArrOfArr,
     *mut CommonParam, // CommonParam
     *mut ProverID,
 );
 
+// Not in cgo/main.h yet, see loader
+pub(crate) type PoisVersionFunc = unsafe extern "C" fn() -> crate::c_types::PoisVersionReturn;
+
+// Go runtime model
+//
+// Every function exported with cgo may be called from any OS thread, the Go
+// runtime attaches the thread on entry. What is not thread safe is the state
+// behind the exports: the verifier is a package level global in the Go
+// library and registering provers or receiving commits writes its maps
+// without any locking. The dynamic loader also hands out the same library,
+// and so the same Go globals, to every load of a path.
+//
+// So calls into Go are serialized by one lock for the whole process, and a
+// PoisLibrary can be shared freely between threads.
+static GO_LOCK: Mutex<()> = Mutex::new(());
+
+pub(crate) fn go_lock() -> MutexGuard<'static, ()> {
+    // A panic while marshalling leaves nothing half written on the Go side
+    crate::utils::lock(&GO_LOCK)
+}
+
+// Absolute path of the Go shared library this crate was built with: the
+// prebuilt cgo/main.so, or the one built from source with build-go. It is
+// only there on the build machine, so it is the last place looked at, and
+// only by debug builds, see loader::library_candidates.
+pub const DEFAULT_LIBRARY_PATH: &str = env!("CESS_POIS_LIB_PATH");
+
+// The Go exports the function types above stand for. With the static
+// feature the Go archive libpois.a is linked into the binary and these
+// replace the symbols looked up in the shared library. Either way they
+// must match the raw ones in sys, or clashing_extern_declarations fails
+// the build.
+#[deny(clashing_extern_declarations)]
+mod linked {
+    use crate::c_types::{CommitC, CommonParam, CommitProofC, GenerateCommitChallengeReturn, I64ArrOfArr, ProverID};
+    use std::os::raw::c_int;
+
+    extern "C" {
+        pub fn GenerateCommitChallenge(
+            commits: *mut CommitC,
+            length: c_int,
+            common_params: *mut CommonParam,
+            prover_id: *mut ProverID,
+        ) -> GenerateCommitChallengeReturn;
+
+        pub fn VerifyCommitAndAccProofs(
+            commit_proofs: *mut *mut CommitProofC,
+            commit_proofs_length: c_int,
+            challenge: *mut I64ArrOfArr,
+            common_params: *mut CommonParam,
+            prover_id: *mut ProverID,
+        );
+    }
+
+    // Only in the archives build.rs finds them in, see link_archive
+    #[cfg(pois_exports)]
+    extern "C" {
+        pub fn PoisVersion() -> crate::c_types::PoisVersionReturn;
+
+        pub fn PoisSetLogger(log: crate::diagnostics::PoisLogFunc);
+
+        pub fn PoisLastError() -> crate::c_types::PoisLastErrorReturn;
+    }
+}
+
+const _: GenerateCommitChallengeFunc = linked::GenerateCommitChallenge;
+const _: VerifyCommitAndAccProofsFunc = linked::VerifyCommitAndAccProofs;
+#[cfg(pois_exports)]
+const _: PoisVersionFunc = linked::PoisVersion;
+#[cfg(pois_exports)]
+const _: PoisSetLoggerFunc = linked::PoisSetLogger;
+#[cfg(pois_exports)]
+const _: PoisLastErrorFunc = linked::PoisLastError;
+
+// The exports newer libraries add, None where this one lacks them
+#[derive(Clone, Copy)]
+pub(crate) struct OptionalExports {
+    pub(crate) version: Option<PoisVersionFunc>,
+    pub(crate) set_logger: Option<PoisSetLoggerFunc>,
+    pub(crate) last_error: Option<PoisLastErrorFunc>,
+}
+
+impl OptionalExports {
+    #[cfg(not(feature = "static"))]
+    fn of(lib: &Library) -> Self {
+        fn get<T: Copy>(lib: &Library, name: &[u8]) -> Option<T> {
+            unsafe { lib.get::<T>(name).ok().map(|symbol| *symbol) }
+        }
+        OptionalExports {
+            version: get(lib, b"PoisVersion"),
+            set_logger: get(lib, b"PoisSetLogger"),
+            last_error: get(lib, b"PoisLastError"),
+        }
+    }
+
+    #[cfg(all(feature = "static", pois_exports))]
+    fn linked() -> Self {
+        OptionalExports {
+            version: Some(linked::PoisVersion),
+            set_logger: Some(linked::PoisSetLogger),
+            last_error: Some(linked::PoisLastError),
+        }
+    }
+
+    #[cfg(all(feature = "static", not(pois_exports)))]
+    fn linked() -> Self {
+        OptionalExports {
+            version: None,
+            set_logger: None,
+            last_error: None,
+        }
+    }
+}
+
+// The Go library, loaded once and shared between threads.
+// With the static feature it is linked in and there is nothing to load.
+pub struct PoisLibrary {
+    #[cfg(not(feature = "static"))]
+    lib: Library,
+    exports: OptionalExports,
+}
+
+impl PoisLibrary {
+    // The path is ignored when the library is linked statically
+    #[cfg(not(feature = "static"))]
+    pub fn load(path: &str) -> Self {
+        Self::from_library(crate::utils::load_library(path))
+    }
+
+    #[cfg(feature = "static")]
+    pub fn load(_path: &str) -> Self {
+        PoisLibrary {
+            exports: OptionalExports::linked(),
+        }
+        .with_logger()
+    }
+
+    // The first library found like discover does, unchecked like load.
+    // Panics if there is none.
+    #[cfg(not(feature = "static"))]
+    pub fn load_default() -> Self {
+        Self::from_library(crate::utils::load_default_library())
+    }
+
+    #[cfg(feature = "static")]
+    pub fn load_default() -> Self {
+        Self::load(DEFAULT_LIBRARY_PATH)
+    }
+
+    #[cfg(not(feature = "static"))]
+    pub(crate) fn from_library(lib: Library) -> Self {
+        let exports = OptionalExports::of(&lib);
+        PoisLibrary { lib, exports }.with_logger()
+    }
+
+    // Looked up once, the pointers live as long as the library
+    pub(crate) fn exports(&self) -> OptionalExports {
+        self.exports
+    }
+
+    // The versions the library reports, None if it predates PoisVersion
+    pub fn version(&self) -> Option<PoisVersion> {
+        let pois_version = self.exports.version?;
+        let version = {
+            let _lock = go_lock();
+            unsafe { pois_version() }
+        };
+        Some(PoisVersion {
+            abi: version.r0 as u32,
+            protocol: version.r1 as u32,
+        })
+    }
+
+    fn with_logger(self) -> Self {
+        diagnostics::install_logger(&self);
+        self
+    }
+
+    #[cfg(not(feature = "static"))]
+    fn generate_commit_challenge_fn(&self) -> GenerateCommitChallengeFunc {
+        unsafe { *self.lib.get(b"GenerateCommitChallenge").expect("Failed to retrieve symbol") }
+    }
+
+    #[cfg(feature = "static")]
+    fn generate_commit_challenge_fn(&self) -> GenerateCommitChallengeFunc {
+        linked::GenerateCommitChallenge
+    }
+
+    #[cfg(not(feature = "static"))]
+    fn verify_commit_and_acc_proofs_fn(&self) -> VerifyCommitAndAccProofsFunc {
+        unsafe { *self.lib.get(b"VerifyCommitAndAccProofs").expect("Failed to retrieve symbol") }
+    }
+
+    #[cfg(feature = "static")]
+    fn verify_commit_and_acc_proofs_fn(&self) -> VerifyCommitAndAccProofsFunc {
+        linked::VerifyCommitAndAccProofs
+    }
+
+    // Panics if Go reports an error, see try_generate_commit_challenge
+    pub fn generate_commit_challenge(
+        &self,
+        commits: &[Commit],
+        common_params: &mut CommonParam,
+        id: &ProverId,
+    ) -> Challenge {
+        self.try_generate_commit_challenge(commits, common_params, id)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    // Commits Go does not accept give an empty challenge, unless the
+    // library reports it as an error, see diagnostics
+    pub fn try_generate_commit_challenge(
+        &self,
+        commits: &[Commit],
+        common_params: &mut CommonParam,
+        id: &ProverId,
+    ) -> Result<Challenge, CallError> {
+        let chals = self.try_generate_commit_challenge_guard(commits, common_params, id)?;
+        Challenge::from_flat(&chals.view().to_vec()).map_err(CallError::Challenge)
+    }
+
+    // Same as generate_commit_challenge but hands back the challenge
+    // as allocated by Go, to be read through its view without copying.
+    pub fn generate_commit_challenge_guard(
+        &self,
+        commits: &[Commit],
+        common_params: &mut CommonParam,
+        id: &ProverId,
+    ) -> I64ArrOfArrGuard {
+        self.try_generate_commit_challenge_guard(commits, common_params, id)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(files = commits.len()))]
+    pub fn try_generate_commit_challenge_guard(
+        &self,
+        commits: &[Commit],
+        common_params: &mut CommonParam,
+        id: &ProverId,
+    ) -> Result<I64ArrOfArrGuard, CallError> {
+        let _timer = Timer::start(telemetry::CHALLENGE_SECONDS);
+        unsafe {
+            let generate_commit_challenge = self.generate_commit_challenge_fn();
+
+            let prover_id = &mut prover_id_c(id.as_bytes());
+
+            // Go copies the commits, so they are freed when the guard drops
+            let mut commits_c = rust_commit_array_to_commit_c_array(commits);
+
+            let (chals, error) = {
+                let _lock = go_lock();
+                let _span = tracing::trace_span!("GenerateCommitChallenge").entered();
+                let chals = generate_commit_challenge(
+                    commits_c.as_mut_ptr(),
+                    commits_c.len() as c_int,
+                    common_params,
+                    prover_id,
+                );
+                (chals, diagnostics::take_error(self))
+            };
+            // Go returns no challenge along with an error
+            if let Some(e) = error {
+                return Err(CallError::Go(e));
+            }
+
+            // Malformed arrays are not freed, see I64ArrOfArrGuard::from_raw
+            let chals = I64ArrOfArrGuard::from_raw(chals.r0, chals.r1, chals.r2).map_err(CallError::Marshal)?;
+            if !chals.view().is_empty() {
+                telemetry::record_challenge();
+            }
+            Ok(chals)
+        }
+    }
+
+    // Whether Go can tell this side its verdict. VerifyCommitAndAccProofs
+    // returns nothing, a library reports proofs that do not verify through
+    // PoisLastError, see diagnostics. Without it no proofs are accepted.
+    pub fn can_verify(&self) -> bool {
+        self.exports.last_error.is_some()
+    }
+
+    // Proofs that do not match the shape of the challenge are rejected
+    // without calling Go, see validate.
+    pub fn verify_commit_and_acc_proofs(
+        &self,
+        commit_proof: Vec<Vec<CommitProof>>,
+        challenge: Challenge,
+        common_params: &mut CommonParam,
+        id: &ProverId,
+    ) -> Result<(), VerifyError> {
+        let result = self.check_and_verify(&commit_proof, &challenge, common_params, id);
+        telemetry::record_verdict(&result);
+        result
+    }
+
+    // Verify the commit proofs of many miners with one library, one call
+    // into Go per miner, one after another as they are serialized anyway,
+    // see GO_LOCK. The Go library has no batched entry point. Only the
+    // checks of validate run in parallel, before the first call.
+    // Each miner is verified on its own, so a failure only rejects that miner.
+    pub fn verify_commit_proofs_sequentially(
+        &self,
+        batch: &[MinerCommitProofs],
+        common_params: &mut CommonParam,
+    ) -> Vec<Result<(), VerifyError>> {
+        let (n, d) = (common_params.n, common_params.d);
+        let checked: Vec<(Result<(), VerifyError>, Duration)> = batch
+            .par_iter()
+            .map(|(_, commit_proof, challenge)| {
+                let start = Instant::now();
+                let result = panic::catch_unwind(AssertUnwindSafe(|| precheck(commit_proof, challenge, n, d)))
+                    .unwrap_or_else(|payload| Err(VerifyError::from_panic(payload)));
+                (result, start.elapsed())
+            })
+            .collect();
+
+        batch
+            .iter()
+            .zip(checked)
+            .map(|((id, commit_proof, challenge), (checked, elapsed))| {
+                // Here rather than on the rayon thread, for a recorder local to this one
+                telemetry::record_seconds(telemetry::PRECHECK_SECONDS, elapsed);
+                let result = checked.and_then(|()| {
+                    panic::catch_unwind(AssertUnwindSafe(|| {
+                        self.verify_one(commit_proof, &challenge.to_flat(), common_params, id.as_bytes())
+                    }))
+                    .unwrap_or_else(|payload| Err(VerifyError::from_panic(payload)))
+                });
+                telemetry::record_verdict(&result);
+                result
+            })
+            .collect()
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(files = commit_proof.len()))]
+    fn check_and_verify(
+        &self,
+        commit_proof: &[Vec<CommitProof>],
+        challenge: &Challenge,
+        common_params: &mut CommonParam,
+        id: &ProverId,
+    ) -> Result<(), VerifyError> {
+        {
+            let _timer = Timer::start(telemetry::PRECHECK_SECONDS);
+            precheck(commit_proof, challenge, common_params.n, common_params.d)?;
+        }
+        self.verify_one(commit_proof, &challenge.to_flat(), common_params, id.as_bytes())
+    }
+
+    fn verify_one(
+        &self,
+        commit_proof: &[Vec<CommitProof>],
+        challenge: &[Vec<i64>],
+        common_params: &mut CommonParam,
+        id: &[u8],
+    ) -> Result<(), VerifyError> {
+        unsafe {
+            let verify_commit_and_acc_proofs = self.verify_commit_and_acc_proofs_fn();
+
+            let prover_id = &mut prover_id_c(id);
+
+            // Go reads the proofs during the call only
+            let mut commit_proof_c = {
+                let _timer = Timer::marshal("commit_proofs");
+                CommitProofCArrayGuard::new(commit_proof)
+            };
+            let length = c_int::try_from(commit_proof_c.len()).expect("too many files for C");
+
+            let mut challenge_c = {
+                let _timer = Timer::marshal("challenge");
+                ArrOfArrBuf::new(challenge.to_vec())
+            };
+
+            let _lock = go_lock();
+            let _span = tracing::trace_span!("VerifyCommitAndAccProofs").entered();
+            // Only the call, not the wait for the lock
+            let _timer = Timer::start(telemetry::VERIFY_SECONDS);
+            verify_commit_and_acc_proofs(
+                commit_proof_c.as_mut_ptr(),
+                length,
+                challenge_c.as_mut_ptr(),
+                common_params,
+                prover_id,
+            );
+            match diagnostics::take_error(self) {
+                Some(e) => Err(VerifyError::Go(e)),
+                None if !self.can_verify() => Err(VerifyError::NoVerdict),
+                None => Ok(()),
+            }
+        }
+    }
+}
+
+// The checks of validate, before anything is passed to Go
+fn precheck(commit_proof: &[Vec<CommitProof>], challenge: &Challenge, n: i64, d: i64) -> Result<(), VerifyError> {
+    tracing::trace_span!("check_commit_proofs")
+        .in_scope(|| check_commit_proofs(commit_proof, challenge, n, d))
+        .map_err(VerifyError::Malformed)
+}
+
+// The ProverID points into id, which must outlive it
+fn prover_id_c(id: &[u8]) -> ProverID {
+    ProverID {
+        id: id.as_ptr() as *mut c_char,
+        length: c_int::try_from(id.len()).expect("prover id too long for C"),
+    }
+}
+
+// Why generating a challenge failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallError {
+    Go(GoError),
+    // Go returned arrays with bad pointers or lengths
+    Marshal(MarshalError),
+    // Go returned a challenge row too short to be one
+    Challenge(ChallengeError),
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallError::Go(e) => write!(f, "{}", e),
+            CallError::Marshal(e) => write!(f, "Go returned a malformed challenge: {}", e),
+            CallError::Challenge(e) => write!(f, "Go returned a malformed challenge: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CallError {}
+
+// Prover ID, its commit proofs and the challenge they answer
+pub type MinerCommitProofs = (ProverId, Vec<Vec<CommitProof>>, Challenge);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyError {
+    // The proofs do not match the shape of the challenge
+    Malformed(ProofShapeError),
+    // The challenge rows are too short to be one
+    Challenge(ChallengeError),
+    // Verifying the miner panicked, e.g. on a malformed array from Go
+    Failed(String),
+    // Go reported an error through PoisLastError, which no library exports yet
+    Go(GoError),
+    // The library cannot report a verdict, see PoisLibrary::can_verify
+    NoVerdict,
+}
+
+impl VerifyError {
+    fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        VerifyError::Failed(panic_message(payload))
+    }
+}
+
+pub(crate) fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "unknown panic".to_string(),
+        },
+    }
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::Malformed(e) => write!(f, "malformed proofs: {}", e),
+            VerifyError::Challenge(e) => write!(f, "malformed challenge: {}", e),
+            VerifyError::Failed(message) => write!(f, "verification failed: {}", message),
+            VerifyError::Go(e) => write!(f, "verification failed: {}", e),
+            VerifyError::NoVerdict => write!(f, "the Go library reports no verdict on commit proofs, none are accepted"),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+// The call_* functions load the library for a single call,
+// use PoisLibrary to load it once.
+
+#[tracing::instrument(level = "debug", skip_all)]
 pub fn call_generate_commit_challenge(
     path: &str,
     commits: &mut [Commit],
     common_params: &mut CommonParam,
     id: &str,
 ) -> Vec<Vec<i64>> {
-    let lib = load_library(path);
-
-    unsafe {
-        let generate_commit_challenge: Symbol<GenerateCommitChallengeFunc> = lib
-            .get(b"GenerateCommitChallenge")
-            .expect("Failed to retrieve symbol");
-
-        let prover_id = &mut ProverID {
-            id: id.as_ptr() as *mut c_char,
-            length: id.len() as i32,
-        };
-        
-        let mut commits_c = rust_commit_array_to_commit_c_array(commits);
-        
-        let (c_arrays, c_lengths, main_array_length) = generate_commit_challenge(
-            commits_c.as_mut_ptr(),
-            commits_c.len() as c_int,
-            common_params,
-            prover_id,
-        );
+    PoisLibrary::load(path)
+        .generate_commit_challenge(commits, common_params, &id.into())
+        .to_flat()
+}
 
-        let challenge = c_ptr_to_i64_array_of_array(c_arrays, c_lengths, main_array_length);
-        challenge
-    }
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn call_generate_commit_challenge_guard(
+    path: &str,
+    commits: &mut [Commit],
+    common_params: &mut CommonParam,
+    id: &str,
+) -> I64ArrOfArrGuard {
+    PoisLibrary::load(path).generate_commit_challenge_guard(commits, common_params, &id.into())
 }
 
+#[tracing::instrument(level = "debug", skip_all)]
 pub fn call_verify_commit_and_acc_proofs(
     path: &str,
     commit_proof: Vec<Vec<CommitProof>>,
     challenge: Vec<Vec<i64>>,
     common_params: &mut CommonParam,
     id: &str,
-) {
-    let lib = load_library(path);
-
-    unsafe{
-        let verify_commit_and_acc_proofs: Symbol<VerifyCommitAndAccProofsFunc> = lib
-            .get(b"VerifyCommitAndAccProofs")
-            .expect("Failed to retrieve symbol");
-
-        let prover_id = &mut ProverID {
-            id: id.as_ptr() as *mut c_char,
-            length: id.len() as i32,
-        };
-
-        let commit_proof_length = commit_proof.len() as c_int;
-        
-        // TODO:
-        // Convert commit_proof: Vec<Vec<CommitProof>>
-        // to **CommitProofC
-
-        let mut challenge_c = i64_array_of_array_to_c_ptr(challenge);
+) -> Result<(), VerifyError> {
+    // Through the checks and telemetry of PoisLibrary, like every verification
+    let challenge = Challenge::try_from(challenge).map_err(VerifyError::Challenge)?;
+    PoisLibrary::load(path).verify_commit_and_acc_proofs(commit_proof, challenge, common_params, &id.into())
+}
 
-        verify_commit_and_acc_proofs(
-            // &mut &mut commit_proof_c as *mut *mut CommitProofC,
-            // commit_proof_length,
-            &mut challenge_c as *mut I64ArrOfArr,
-            common_params,
-            prover_id
-        );
-    }
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn call_verify_commit_proofs_sequentially(
+    path: &str,
+    batch: &[MinerCommitProofs],
+    common_params: &mut CommonParam,
+) -> Vec<Result<(), VerifyError>> {
+    PoisLibrary::load(path).verify_commit_proofs_sequentially(batch, common_params)
 }
@@ -0,0 +1,283 @@
+use std::sync::Arc;
+
+use tonic::{Request, Response, Status};
+
+use crate::{
+    service::{ServiceError, VerifierService},
+    session::SessionError,
+    types::{
+        AccWitnessNode, Challenge, Commit, CommitChallenge, CommitProof, DeletionProof, MhtProof,
+        ProverId, SpaceProof,
+    },
+};
+
+// gRPC API of VerifierService, defined in proto/pois.proto.
+//
+// The messages mirror types.rs and are converted with From both ways.
+// Serve it with tonic:
+//
+//     Server::builder().add_service(grpc::server(service)).serve(addr)
+
+#[allow(clippy::all)]
+pub mod proto {
+    tonic::include_proto!("cess.pois.v1");
+}
+
+use proto::verifier_server::{Verifier, VerifierServer};
+
+pub fn server(service: Arc<VerifierService>) -> VerifierServer<GrpcVerifier> {
+    VerifierServer::new(GrpcVerifier(service))
+}
+
+pub struct GrpcVerifier(Arc<VerifierService>);
+
+#[tonic::async_trait]
+impl Verifier for GrpcVerifier {
+    async fn register(&self, request: Request<proto::RegisterRequest>) -> Result<Response<proto::Empty>, Status> {
+        let request = request.into_inner();
+        self.0.register(request.prover_id.into()).await.map_err(status)?;
+        Ok(Response::new(proto::Empty {}))
+    }
+
+    async fn submit_commits(
+        &self,
+        request: Request<proto::SubmitCommitsRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let request = request.into_inner();
+        let commits = request.commits.into_iter().map(Commit::from).collect();
+        self.0
+            .submit_commits(&request.prover_id.into(), commits)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(proto::Empty {}))
+    }
+
+    async fn get_challenge(
+        &self,
+        request: Request<proto::GetChallengeRequest>,
+    ) -> Result<Response<proto::Challenge>, Status> {
+        let id = ProverId::from(request.into_inner().prover_id);
+        let challenge = self.0.get_challenge(&id).await.map_err(status)?;
+        Ok(Response::new(challenge.into()))
+    }
+
+    async fn submit_commit_proofs(
+        &self,
+        request: Request<proto::SubmitCommitProofsRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let request = request.into_inner();
+        let proofs = request
+            .files
+            .into_iter()
+            .map(|file| file.proofs.into_iter().map(CommitProof::from).collect())
+            .collect();
+        self.0
+            .submit_commit_proofs(&request.prover_id.into(), proofs)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(proto::Empty {}))
+    }
+
+    async fn verify_space(
+        &self,
+        request: Request<proto::VerifySpaceRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let request = request.into_inner();
+        let proof = SpaceProof::from(request.proof.ok_or_else(|| Status::invalid_argument("missing proof"))?);
+        self.0
+            .verify_space(&request.prover_id.into(), &proof)
+            .map_err(status)?;
+        Ok(Response::new(proto::Empty {}))
+    }
+
+    async fn verify_deletion(
+        &self,
+        request: Request<proto::VerifyDeletionRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let request = request.into_inner();
+        let proof = DeletionProof::from(request.proof.ok_or_else(|| Status::invalid_argument("missing proof"))?);
+        self.0
+            .verify_deletion(&request.prover_id.into(), &proof)
+            .map_err(status)?;
+        Ok(Response::new(proto::Empty {}))
+    }
+}
+
+fn status(e: ServiceError) -> Status {
+    let message = e.to_string();
+    match e {
+        ServiceError::UnknownMiner => Status::not_found(message),
+        ServiceError::AlreadyRegistered => Status::already_exists(message),
+        ServiceError::CommitsRejected | ServiceError::Session(SessionError::Rejected(_)) => {
+            Status::invalid_argument(message)
+        }
+        ServiceError::Session(SessionError::DeadlineExpired { .. }) => Status::deadline_exceeded(message),
+        ServiceError::Session(_) => Status::failed_precondition(message),
+        ServiceError::Unsupported(_) => Status::unimplemented(message),
+        ServiceError::Store(_) | ServiceError::Internal(_) => Status::internal(message),
+    }
+}
+
+impl From<Commit> for proto::Commit {
+    fn from(commit: Commit) -> Self {
+        proto::Commit {
+            file_index: commit.file_index,
+            roots: commit.roots,
+        }
+    }
+}
+
+impl From<proto::Commit> for Commit {
+    fn from(commit: proto::Commit) -> Self {
+        Commit {
+            file_index: commit.file_index,
+            roots: commit.roots,
+        }
+    }
+}
+
+impl From<Challenge> for proto::Challenge {
+    fn from(challenge: Challenge) -> Self {
+        proto::Challenge {
+            files: challenge
+                .0
+                .into_iter()
+                .map(|c| proto::CommitChallenge {
+                    file_index: c.file_index,
+                    layer_selector: c.layer_selector,
+                    node_indices: c.node_indices,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<proto::Challenge> for Challenge {
+    fn from(challenge: proto::Challenge) -> Self {
+        Challenge(
+            challenge
+                .files
+                .into_iter()
+                .map(|c| CommitChallenge {
+                    file_index: c.file_index,
+                    layer_selector: c.layer_selector,
+                    node_indices: c.node_indices,
+                })
+                .collect(),
+        )
+    }
+}
+
+impl From<MhtProof> for proto::MhtProof {
+    fn from(proof: MhtProof) -> Self {
+        proto::MhtProof {
+            index: proof.index,
+            label: proof.label,
+            paths: proof.paths,
+            locs: proof.locs,
+        }
+    }
+}
+
+impl From<proto::MhtProof> for MhtProof {
+    fn from(proof: proto::MhtProof) -> Self {
+        MhtProof {
+            index: proof.index,
+            label: proof.label,
+            paths: proof.paths,
+            locs: proof.locs,
+        }
+    }
+}
+
+impl From<CommitProof> for proto::CommitProof {
+    fn from(proof: CommitProof) -> Self {
+        proto::CommitProof {
+            node: proof.node.map(Into::into),
+            parents: proof.parents.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<proto::CommitProof> for CommitProof {
+    fn from(proof: proto::CommitProof) -> Self {
+        CommitProof {
+            node: proof.node.map(Into::into),
+            parents: proof.parents.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<AccWitnessNode> for proto::AccWitnessNode {
+    fn from(node: AccWitnessNode) -> Self {
+        proto::AccWitnessNode {
+            elem: node.elem,
+            wit: node.wit,
+            acc: node.acc.map(|acc| Box::new((*acc).into())),
+        }
+    }
+}
+
+impl From<proto::AccWitnessNode> for AccWitnessNode {
+    fn from(node: proto::AccWitnessNode) -> Self {
+        AccWitnessNode {
+            elem: node.elem,
+            wit: node.wit,
+            acc: node.acc.map(|acc| Box::new((*acc).into())),
+        }
+    }
+}
+
+impl From<SpaceProof> for proto::SpaceProof {
+    fn from(proof: SpaceProof) -> Self {
+        proto::SpaceProof {
+            left: proof.left,
+            right: proof.right,
+            proofs: proof
+                .proofs
+                .into_iter()
+                .map(|proofs| proto::MhtProofs {
+                    proofs: proofs.into_iter().map(Into::into).collect(),
+                })
+                .collect(),
+            roots: proof.roots,
+            wit_chains: proof.wit_chains.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<proto::SpaceProof> for SpaceProof {
+    fn from(proof: proto::SpaceProof) -> Self {
+        SpaceProof {
+            left: proof.left,
+            right: proof.right,
+            proofs: proof
+                .proofs
+                .into_iter()
+                .map(|p| p.proofs.into_iter().map(Into::into).collect())
+                .collect(),
+            roots: proof.roots,
+            wit_chains: proof.wit_chains.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<DeletionProof> for proto::DeletionProof {
+    fn from(proof: DeletionProof) -> Self {
+        proto::DeletionProof {
+            roots: proof.roots,
+            wit_chain: proof.wit_chain.map(Into::into),
+            acc_path: proof.acc_path,
+        }
+    }
+}
+
+impl From<proto::DeletionProof> for DeletionProof {
+    fn from(proof: proto::DeletionProof) -> Self {
+        DeletionProof {
+            roots: proof.roots,
+            wit_chain: proof.wit_chain.map(Into::into),
+            acc_path: proof.acc_path,
+        }
+    }
+}
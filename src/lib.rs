@@ -1,26 +1,51 @@
+#[cfg(feature = "async")]
+pub mod async_ffi;
+pub mod backend;
 pub mod c_types;
+pub mod diagnostics;
 pub mod ffi;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod loader;
+pub mod merkle;
+pub mod params;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+#[cfg(feature = "async")]
+pub mod service;
+pub mod session;
+pub mod store;
+pub mod sys;
+pub mod telemetry;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod types;
 pub mod utils;
+pub mod validate;
+pub mod views;
+// The examples dlopen main.so, which the static archives do not replace
+#[cfg(not(feature = "static"))]
 pub mod examples;
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        ffi::{call_generate_commit_challenge, call_verify_commit_and_acc_proofs},
-        utils::{rsa_keygen, init_common_params}, examples::call_return_an_array, types::{Commit, MhtProof, CommitProof}, c_types::CommonParam,
+        ffi::{call_generate_commit_challenge, call_verify_commit_and_acc_proofs, CallError, PoisLibrary, MinerCommitProofs, VerifyError, DEFAULT_LIBRARY_PATH},
+        validate::{check_commit_proofs, ProofShapeError},
+        merkle::{check_path_shape, path_root, verify_path, PathError},
+        session::{MinerSession, MinerState, SessionError},
+        store::{export_prover_node, import_prover_node, rehydrate_sessions, MemoryStore, ProverNodeRecord, StoreError, VerifierStore},
+        utils::{rsa_keygen, init_common_params},
+        types::{Commit, MhtProof, CommitProof, ProverId, RsaKey, Challenge, CommitChallenge}, c_types::CommonParam,
+        params::{PoisParams, ParamsError},
+        testing::{make_commits, shaped_proofs},
+        utils::{rust_commit_array_to_commit_c_array, c_ptr_to_i64_array_of_array, commit_c_array_to_rust_commit_array},
+        views::{ArrOfArrBuf, ArrOfArrView, I64MatrixView, MarshalError}, c_types::CommitC,
     };
     // Initialize the common parameters.
     // RsaKey and k, n, and d value of expander graph.
     fn init_params() -> CommonParam{
-        let rsa_key = rsa_keygen(2048);
-      
-        let k: i64 = 7;
-        // let n: i64 = 1024 * 1024 * 4;
-        let n: i64 = 512;
-        let d: i64 = 64;
-    
-        init_common_params(rsa_key, k, n, d)
+        PoisParams::devnet().to_common_param()
     }
 
     #[test]
@@ -49,7 +74,7 @@ mod tests {
         ];
         
         let id = "test miner id";
-        let path = "cgo/main.so";
+        let path = DEFAULT_LIBRARY_PATH;
 
         let chal = call_generate_commit_challenge(
             path,
@@ -93,20 +118,1040 @@ mod tests {
             vec![4, 4059, 21, 24, 35, 53, 47, 4, 1],
         ];
 
-        let path = "cgo/main.so";
+        let path = DEFAULT_LIBRARY_PATH;
 
-        call_verify_commit_and_acc_proofs(
+        // Checked before Go sees them
+        let verdict = call_verify_commit_and_acc_proofs(
             path,
             commit_proof_arr_of_arr,
-            challenge,
+            challenge.clone(),
             &mut common_param,
             id,
         );
+        assert!(matches!(verdict, Err(VerifyError::Malformed(_))));
+
+        let verdict = call_verify_commit_and_acc_proofs(path, vec![], vec![vec![1]], &mut common_param, id);
+        assert!(matches!(verdict, Err(VerifyError::Challenge(_))));
+
+        // The prebuilt library takes well shaped proofs but reports no verdict
+        let shaped = shaped_proofs(&Challenge::from_flat(&challenge).unwrap(), common_param.n, common_param.d);
+        let verdict = call_verify_commit_and_acc_proofs(path, shaped, challenge, &mut common_param, id);
+        assert_eq!(verdict, Err(VerifyError::NoVerdict));
+    }
+
+    #[test]
+    fn test_verify_commit_proofs_sequentially() {
+        let params = PoisParams::devnet();
+        let mut common_param = params.to_common_param();
+        let lib = PoisLibrary::load_default();
+
+        let mut batch: Vec<MinerCommitProofs> = (0..5)
+            .map(|i| {
+                let id = ProverId::from(format!("batch miner {}", i));
+                let chal = lib.generate_commit_challenge(&make_commits(params.k, 4, i), &mut common_param, &id);
+                let proofs = shaped_proofs(&chal, params.n, params.d);
+                (id, proofs, chal)
+            })
+            .collect();
+        batch[1].1.pop();
+        batch[2].1[0][0].node.as_mut().unwrap().index += 1;
+        // Well shaped but tampered with, only Go can tell
+        batch[3].1[0][0].node.as_mut().unwrap().label[0] ^= 1;
+        batch[4].1[1][2].parents[3].paths[0][0] ^= 1;
+
+        let verdicts = lib.verify_commit_proofs_sequentially(&batch, &mut common_param);
+        assert!(matches!(verdicts[1], Err(VerifyError::Malformed(ProofShapeError::MissingFile { file_index: 4 }))));
+        assert!(matches!(verdicts[2], Err(VerifyError::Malformed(ProofShapeError::IndexMismatch { file_index: 1, position: 0, .. }))));
+        assert!(verdicts[3].is_err());
+        assert!(verdicts[4].is_err());
+
+        // The prebuilt library reports no verdict, so none is accepted,
+        // the honest miner included
+        assert!(!lib.can_verify());
+        for verdict in [&verdicts[0], &verdicts[3], &verdicts[4]] {
+            assert_eq!(verdict, &Err(VerifyError::NoVerdict));
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_metrics() {
+        use crate::telemetry::{CHALLENGES_GENERATED, CHALLENGE_SECONDS, MARSHAL_SECONDS, PRECHECK_SECONDS, PROOFS_REJECTED, PROOFS_VERIFIED, VERIFY_SECONDS};
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+        let params = PoisParams::devnet();
+        let mut common_param = params.to_common_param();
+        let lib = PoisLibrary::load_default();
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        metrics::with_local_recorder(&recorder, || {
+            let mut batch: Vec<MinerCommitProofs> = (0..3)
+                .map(|i| {
+                    let id = ProverId::from(format!("metrics miner {}", i));
+                    let chal = lib.generate_commit_challenge(&make_commits(params.k, 4, i), &mut common_param, &id);
+                    let proofs = shaped_proofs(&chal, params.n, params.d);
+                    (id, proofs, chal)
+                })
+                .collect();
+            batch[1].1.pop();
+            batch[2].1.pop();
+            lib.verify_commit_proofs_sequentially(&batch, &mut common_param);
+        });
+
+        let metrics: Vec<_> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| {
+                let key = key.key();
+                let labels: Vec<_> = key.labels().map(|l| format!("{}={}", l.key(), l.value())).collect();
+                (key.name().to_string(), labels, value)
+            })
+            .collect();
+        let value = |name: &str, labels: &[&str]| {
+            metrics.iter().find(|(n, l, _)| n == name && l == labels).map(|(_, _, v)| v)
+        };
+
+        assert_eq!(value(CHALLENGES_GENERATED, &[]), Some(&DebugValue::Counter(3)));
+        assert_eq!(value(PROOFS_VERIFIED, &[]), None);
+        assert_eq!(value(PROOFS_REJECTED, &["reason=missing_file"]), Some(&DebugValue::Counter(2)));
+        assert_eq!(value(PROOFS_REJECTED, &["reason=no_verdict"]), Some(&DebugValue::Counter(1)));
+        assert!(matches!(value(CHALLENGE_SECONDS, &[]), Some(DebugValue::Histogram(h)) if h.len() == 3));
+        assert!(matches!(value(MARSHAL_SECONDS, &["what=commits"]), Some(DebugValue::Histogram(h)) if h.len() == 3));
+        // Every miner is checked, only the one well shaped goes to Go
+        assert!(matches!(value(PRECHECK_SECONDS, &[]), Some(DebugValue::Histogram(h)) if h.len() == 3));
+        assert!(matches!(value(VERIFY_SECONDS, &[]), Some(DebugValue::Histogram(h)) if h.len() == 1));
+    }
+
+    #[test]
+    fn test_check_commit_proofs() {
+        let (n, d) = (512, 64);
+        let challenge = Challenge::from_flat(&[
+            vec![1, 3903, 17, 49, 28, 8, 15, 64, 29],
+            vec![2, 3818, 60, 39, 27, 42, 26, 46, 56],
+        ]).unwrap();
+        let proofs = shaped_proofs(&challenge, n, d);
+        assert_eq!(check_commit_proofs(&proofs, &challenge, n, d), Ok(()));
+
+        let check = |edit: &dyn Fn(&mut Vec<Vec<CommitProof>>)| {
+            let mut proofs = shaped_proofs(&challenge, n, d);
+            edit(&mut proofs);
+            check_commit_proofs(&proofs, &challenge, n, d).unwrap_err()
+        };
+
+        assert_eq!(check(&|p| { p.pop(); }), ProofShapeError::MissingFile { file_index: 2 });
+        assert_eq!(check(&|p| p.push(vec![])), ProofShapeError::ExtraFiles { expected: 2, found: 3 });
+        assert_eq!(check(&|p| { p[1].pop(); }), ProofShapeError::NodeCount { file_index: 2, expected: 8, found: 7 });
+        assert_eq!(check(&|p| p[0][3].node = None), ProofShapeError::MissingNode { file_index: 1, position: 3 });
+        assert!(matches!(
+            check(&|p| p[0][2].node.as_mut().unwrap().index = 7),
+            ProofShapeError::IndexMismatch { file_index: 1, position: 2, found: 7, .. }
+        ));
+        assert_eq!(check(&|p| { p[1][0].parents.pop(); }), ProofShapeError::ParentCount { file_index: 2, position: 0, expected: 65, found: 64 });
+
+        // The last proof is in layer 0 and has no parents
+        assert_eq!(check(&|p| {
+            let parent = p[0][0].parents[0].index;
+            p[0][7].parents.push(MhtProof { index: parent, label: vec![], paths: vec![], locs: vec![] });
+        }), ProofShapeError::ParentCount { file_index: 1, position: 7, expected: 0, found: 1 });
+        assert_eq!(
+            check(&|p| p[1][2].parents[5].locs.clear()),
+            ProofShapeError::BadPath { file_index: 2, position: 2, error: PathError::LocsMismatch { paths: 1, locs: 0 } }
+        );
+
+        let bad_selector = Challenge::from_flat(&[vec![1, 3903, 65, 0, 0, 0, 0, 0, 0]]).unwrap();
+        let proofs = shaped_proofs(&Challenge::from_flat(&[vec![1, 3903, 0, 0, 0, 0, 0, 0, 0]]).unwrap(), n, d);
+        assert_eq!(
+            check_commit_proofs(&proofs, &bad_selector, n, d),
+            Err(ProofShapeError::InvalidSelector { file_index: 1, position: 0, selector: 65 })
+        );
+        // Fixtures for a bad selector, rejected rather than panicking
+        assert_eq!(
+            check_commit_proofs(&shaped_proofs(&bad_selector, n, d), &bad_selector, n, d),
+            Err(ProofShapeError::InvalidSelector { file_index: 1, position: 0, selector: 65 })
+        );
+    }
+
+    #[test]
+    fn test_merkle_path() {
+        use sha2::{Digest, Sha512};
+
+        let hash = |left: &[u8], right: &[u8]| Sha512::new().chain_update(left).chain_update(right).finalize().to_vec();
+        let leaves: Vec<Vec<u8>> = (0..4u8).map(|i| vec![i; 64]).collect();
+        let (left, right) = (hash(&leaves[0], &leaves[1]), hash(&leaves[2], &leaves[3]));
+        let root = hash(&left, &right);
+
+        // Leaf 2 is a left child, its parent a right child
+        let mut proof = MhtProof { index: 2, label: leaves[2].clone(), paths: vec![leaves[3].clone(), left], locs: vec![0, 1] };
+        assert_eq!(verify_path(&root, &proof), Ok(()));
+        assert_eq!(verify_path(&right, &proof), Err(PathError::RootMismatch));
+        assert_eq!(path_root(&MhtProof { paths: vec![], locs: vec![], ..proof.clone() }), Ok(leaves[2].clone()));
+
+        proof.locs[1] = 2;
+        assert_eq!(verify_path(&root, &proof), Err(PathError::InvalidLoc { level: 1, loc: 2 }));
+        proof.locs.pop();
+        assert_eq!(check_path_shape(&proof), Err(PathError::LocsMismatch { paths: 2, locs: 1 }));
+        proof.locs.push(1);
+
+        // Sizes are bounded before anything is hashed or copied
+        proof.paths[1].push(0);
+        assert_eq!(check_path_shape(&proof), Err(PathError::NodeSize { level: 1, size: 65 }));
+        proof.paths[1].pop();
+        let huge_label = MhtProof { label: vec![0; 1 << 20], ..proof.clone() };
+        assert_eq!(verify_path(&root, &huge_label), Err(PathError::LabelSize { size: 1 << 20 }));
+        let deep = MhtProof { paths: vec![vec![0; 64]; 65], locs: vec![0; 65], ..proof };
+        assert_eq!(verify_path(&root, &deep), Err(PathError::TooDeep { depth: 65 }));
+    }
+
+    #[test]
+    fn test_miner_session() {
+        use std::time::{Duration, Instant};
+
+        let (k, n, d) = (7, 512, 64);
+        let now = Instant::now();
+        let deadline = now + Duration::from_secs(60);
+        let commits = make_commits(k, 2, 1);
+        let challenge = Challenge::from_flat(&[
+            vec![1, 3903, 17, 49, 28, 8, 15, 64, 29],
+            vec![2, 3818, 60, 39, 27, 42, 26, 46, 56],
+        ]).unwrap();
+        let accept = |_: &ProverId, proofs: Vec<Vec<CommitProof>>, chal: Challenge| {
+            check_commit_proofs(&proofs, &chal, n, d).map_err(VerifyError::Malformed)
+        };
+
+        let mut session = MinerSession::register("test miner id".into());
+        assert_eq!(session.state(), MinerState::Registered);
+
+        // Out of order
+        assert_eq!(
+            session.challenge(challenge.clone(), deadline),
+            Err(SessionError::InvalidTransition { from: MinerState::Registered, action: "challenge" })
+        );
+        assert!(matches!(
+            session.submit_commit_proofs(vec![], now, accept),
+            Err(SessionError::InvalidTransition { from: MinerState::Registered, .. })
+        ));
+
+        session.receive_commits(commits.clone()).unwrap();
+        assert_eq!(session.state(), MinerState::CommitsReceived);
+        assert_eq!(session.challenge(Challenge(challenge.0[..1].to_vec()), deadline), Err(SessionError::ChallengeMismatch));
+
+        // Late proofs send the miner back to register its commits again
+        session.challenge(challenge.clone(), deadline).unwrap();
+        assert_eq!(session.pending_challenge().unwrap().deadline, deadline);
+        let late = deadline + Duration::from_secs(1);
+        assert_eq!(
+            session.submit_commit_proofs(shaped_proofs(&challenge, n, d), late, accept),
+            Err(SessionError::DeadlineExpired { deadline })
+        );
+        assert_eq!(session.state(), MinerState::Registered);
+        assert!(session.pending_challenge().is_none());
+
+        // So do rejected proofs
+        session.receive_commits(commits.clone()).unwrap();
+        session.challenge(challenge.clone(), deadline).unwrap();
+        assert!(matches!(
+            session.submit_commit_proofs(vec![], now, accept),
+            Err(SessionError::Rejected(VerifyError::Malformed(ProofShapeError::MissingFile { file_index: 1 })))
+        ));
+        assert_eq!(session.state(), MinerState::Registered);
+
+        // Verified in two steps, a miner logging out meanwhile drops the verdict
+        session.receive_commits(commits.clone()).unwrap();
+        session.challenge(challenge.clone(), deadline).unwrap();
+        assert_eq!(session.begin_commit_proofs(now), Ok(challenge.clone()));
+        assert_eq!(session.state(), MinerState::Verifying);
+        assert!(matches!(
+            session.begin_commit_proofs(now),
+            Err(SessionError::InvalidTransition { from: MinerState::Verifying, .. })
+        ));
+        session.logout().unwrap();
+        assert!(matches!(
+            session.finish_commit_proofs(Ok(())),
+            Err(SessionError::InvalidTransition { from: MinerState::LoggedOut, .. })
+        ));
+        let mut session = MinerSession::register("test miner id".into());
+
+        session.receive_commits(commits.clone()).unwrap();
+        session.challenge(challenge.clone(), deadline).unwrap();
+        session.submit_commit_proofs(shaped_proofs(&challenge, n, d), now, accept).unwrap();
+        assert_eq!(session.state(), MinerState::Proven);
+        assert_eq!(session.count(), 2);
+
+        session.space_audited().unwrap();
+        assert_eq!(session.state(), MinerState::SpaceAudited);
+
+        // Files 1 and 2 are proven already
+        assert_eq!(session.receive_commits(commits), Err(SessionError::StaleCommit { file_index: 1, count: 2 }));
+
+        session.logout().unwrap();
+        assert!(matches!(
+            session.receive_commits(make_commits(k, 3, 1)),
+            Err(SessionError::InvalidTransition { from: MinerState::LoggedOut, .. })
+        ));
+        assert!(session.logout().is_err());
+    }
+
+    fn check_verifier_store(store: &dyn VerifierStore) {
+        // Written before the state was stored
+        let proven = ProverNodeRecord {
+            id: "proven miner".into(),
+            count: 2,
+            commits: vec![],
+            state: None,
+        };
+        let mut session = MinerSession::register("committed miner".into());
+        session.receive_commits(make_commits(7, 2, 2)).unwrap();
+        let challenge = Challenge::from_flat(&[vec![1, 3903, 17, 49, 28, 8, 15, 64, 29], vec![2, 3818, 60, 39, 27, 42, 26, 46, 56]]).unwrap();
+        session.challenge(challenge, std::time::Instant::now()).unwrap();
+        // Proven, then logged out
+        let mut logged_out = MinerSession::restore(ProverNodeRecord { id: "logged out miner".into(), ..proven.clone() });
+        logged_out.logout().unwrap();
+
+        store.put(&proven).unwrap();
+        store.put(&session.to_record()).unwrap();
+        store.put(&logged_out.to_record()).unwrap();
+        assert_eq!(store.get(&proven.id).unwrap(), Some(proven.clone()));
+        assert_eq!(store.get(&"unknown miner".into()).unwrap(), None);
+
+        let sessions = rehydrate_sessions(store).unwrap();
+        assert_eq!(sessions.len(), 3);
+        let restored = &sessions[&proven.id];
+        assert_eq!(restored.state(), MinerState::Proven);
+        assert_eq!(restored.count(), 2);
+        // The pending challenge is lost, the commits are challenged again
+        let restored = &sessions[session.id()];
+        assert_eq!(restored.state(), MinerState::CommitsReceived);
+        assert_eq!(restored.commits(), session.commits());
+        let restored = &sessions[logged_out.id()];
+        assert_eq!(restored.state(), MinerState::LoggedOut);
+        assert_eq!(restored.count(), 2);
+
+        store.remove(&proven.id).unwrap();
+        store.remove(logged_out.id()).unwrap();
+        assert_eq!(store.load_all().unwrap(), vec![session.to_record()]);
+    }
+
+    #[test]
+    fn test_verifier_store() {
+        check_verifier_store(&MemoryStore::new());
+
+        #[cfg(feature = "sled")]
+        {
+            use crate::store::SledStore;
+
+            let dir = tempfile::tempdir().unwrap();
+            let record = {
+                let store = SledStore::open(dir.path()).unwrap();
+                check_verifier_store(&store);
+                store.load_all().unwrap()
+            };
+            // Survives reopening
+            let store = SledStore::open(dir.path()).unwrap();
+            assert_eq!(store.load_all().unwrap(), record);
+        }
+    }
+
+    #[test]
+    fn test_prover_node_snapshot() {
+        let (from, to) = (MemoryStore::new(), MemoryStore::new());
+        let mut session = MinerSession::register("migrated miner".into());
+        session.receive_commits(make_commits(7, 1, 3)).unwrap();
+        from.put(&session.to_record()).unwrap();
+
+        let snapshot = export_prover_node(&from, session.id()).unwrap();
+        assert_eq!(import_prover_node(&to, &snapshot).unwrap(), *session.id());
+        assert_eq!(to.get(session.id()).unwrap(), Some(session.to_record()));
+
+        assert!(matches!(export_prover_node(&from, &"unknown miner".into()), Err(StoreError::NotFound(_))));
+        assert!(matches!(import_prover_node(&to, b"not json"), Err(StoreError::Encoding(_))));
+        // Version 1 still had the accumulator, which is ignored
+        let mut old: serde_json::Value = serde_json::from_slice(&snapshot).unwrap();
+        old["version"] = 1.into();
+        old["node"]["acc"] = vec![5u8; 256].into();
+        let (to, old) = (MemoryStore::new(), serde_json::to_vec(&old).unwrap());
+        assert_eq!(import_prover_node(&to, &old).unwrap(), *session.id());
+        assert_eq!(to.get(session.id()).unwrap(), Some(session.to_record()));
+
+        let newer = String::from_utf8(snapshot).unwrap().replace("\"version\":2", "\"version\":3");
+        assert!(matches!(
+            import_prover_node(&to, newer.as_bytes()),
+            Err(StoreError::UnsupportedSnapshot { version: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_concurrent_generate_commit_challenge() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<PoisLibrary>();
+
+        let params = PoisParams::devnet();
+        let rsa_key = rsa_keygen(params.key_bits);
+        let lib = PoisLibrary::load_default();
+
+        let threads = 8;
+        let calls = 4;
+        std::thread::scope(|s| {
+            for t in 0..threads {
+                let (lib, rsa_key, params) = (&lib, rsa_key.clone(), &params);
+                s.spawn(move || {
+                    let mut common_param = init_common_params(rsa_key, params.k, params.n, params.d);
+                    for c in 0..calls {
+                        let commits = make_commits(params.k, 4, (t * calls + c) as u8);
+                        let id = ProverId::from(format!("stress miner {} {}", t, c));
+
+                        let chal = lib.generate_commit_challenge(&commits, &mut common_param, &id);
+
+                        assert_eq!(chal.0.len(), commits.len());
+                        for (file_chal, commit) in chal.0.iter().zip(&commits) {
+                            assert_eq!(file_chal.file_index, commit.file_index);
+                            assert_eq!(file_chal.node_indices.len() as i64, params.k);
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_async_generate_commit_challenge() {
+        use crate::async_ffi::{AsyncCallError, AsyncPoisLibrary, OwnedCommonParam};
+        use std::{sync::Arc, time::Duration};
+
+        let params = PoisParams::devnet();
+        let common_params = Arc::new(OwnedCommonParam::from_params(&params, rsa_keygen(params.key_bits)));
+        let lib = AsyncPoisLibrary::new(PoisLibrary::load_default(), 2);
+
+        let calls = (0..4).map(|i| {
+            let (lib, common_params) = (lib.clone(), common_params.clone());
+            let commits = make_commits(params.k, 4, i);
+            tokio::spawn(async move {
+                lib.generate_commit_challenge(commits, common_params, format!("async miner {}", i).into()).await
+            })
+        });
+        for call in calls.collect::<Vec<_>>() {
+            let chal = call.await.unwrap().unwrap();
+            assert_eq!(chal.0.iter().map(|c| c.file_index).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        }
+
+        // No slot ever frees up, so the call times out waiting for one
+        let lib = AsyncPoisLibrary::new(PoisLibrary::load_default(), 0).with_timeout(Duration::from_millis(50));
+        let result = lib
+            .generate_commit_challenge(make_commits(params.k, 4, 9), common_params, "async miner timeout".into())
+            .await;
+        assert!(matches!(result, Err(AsyncCallError::Timeout)), "{:?}", result);
+    }
+
+    // A key that is enough for the mock, which never looks at it
+    fn mock_rsa_key() -> RsaKey {
+        RsaKey { n: 3233u32.into(), g: 2u32.into() }
+    }
+
+    #[test]
+    fn test_mock_backend() {
+        use crate::{backend::{MockBackend, MockCall, PoisBackend}, diagnostics::{GoError, GO_ERROR_PANIC}};
+
+        let mock = MockBackend::new();
+        let challenge = Challenge::from_flat(&[vec![1, 3903, 17, 49, 28, 8, 15, 64, 29]]).unwrap();
+        mock.push_challenge(Ok(challenge.clone()));
+        mock.push_challenge(Err(CallError::Go(GoError::from_code(GO_ERROR_PANIC, "boom".into()))));
+        mock.push_verdict(Err(VerifyError::Failed("scripted".into())));
+
+        let backend: &dyn PoisBackend = &mock;
+        let mut common_params = init_common_params(mock_rsa_key(), 7, 512, 64);
+        let (id, commits) = (ProverId::from("mock miner"), make_commits(7, 1, 1));
+        assert_eq!(backend.try_generate_commit_challenge(&commits, &mut common_params, &id), Ok(challenge.clone()));
+        assert!(backend.try_generate_commit_challenge(&commits, &mut common_params, &id).is_err());
+        // Out of script: no challenge, proofs accepted
+        assert_eq!(backend.try_generate_commit_challenge(&[], &mut common_params, &id), Ok(Challenge::default()));
+        assert_eq!(
+            backend.verify_commit_and_acc_proofs(vec![], challenge.clone(), &mut common_params, &id),
+            Err(VerifyError::Failed("scripted".into()))
+        );
+        assert_eq!(backend.verify_commit_and_acc_proofs(vec![], challenge.clone(), &mut common_params, &id), Ok(()));
+
+        assert_eq!(mock.remaining(), (0, 0));
+        let calls = mock.calls();
+        assert_eq!(calls.len(), 5);
+        assert_eq!(calls[0], MockCall::GenerateCommitChallenge { id: id.clone(), commits });
+        assert_eq!(calls[4], MockCall::VerifyCommitAndAccProofs { id, commit_proof: vec![], challenge });
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_service_with_mock_backend() {
+        use std::sync::Arc;
+        use crate::{
+            async_ffi::OwnedCommonParam, backend::{MockBackend, MockCall}, diagnostics::{GoError, GO_ERROR_INVALID_INPUT},
+            service::{ServiceError, VerifierService},
+        };
+
+        let (n, d) = (512, 64);
+        let mock = Arc::new(MockBackend::new());
+        let service = VerifierService::with_backend(mock.clone(), OwnedCommonParam::new(mock_rsa_key(), 7, n, d));
+        let id = ProverId::from("mock service miner");
+        let commits = make_commits(7, 2, 1);
+        let challenge = Challenge::from_flat(&[
+            vec![1, 3903, 17, 49, 28, 8, 15, 64, 29],
+            vec![2, 3818, 60, 39, 27, 42, 26, 46, 56],
+        ]).unwrap();
+
+        mock.push_challenge(Err(CallError::Go(GoError::from_code(GO_ERROR_INVALID_INPUT, "bad roots".into()))));
+        mock.push_challenge(Ok(challenge.clone()));
+        mock.push_verdict(Err(VerifyError::Failed("scripted".into())));
+
+        service.register(id.clone()).await.unwrap();
+        service.submit_commits(&id, commits.clone()).await.unwrap();
+        assert!(matches!(service.get_challenge(&id).await, Err(ServiceError::CommitsRejected)));
+        assert_eq!(service.get_challenge(&id).await.unwrap(), challenge);
+
+        // Rejected, so the commits are submitted again, then accepted
+        let proofs = shaped_proofs(&challenge, n, d);
+        let result = service.submit_commit_proofs(&id, proofs.clone()).await;
+        assert!(matches!(result, Err(ServiceError::Session(SessionError::Rejected(VerifyError::Failed(_))))), "{:?}", result);
+        service.submit_commits(&id, commits).await.unwrap();
+        mock.push_challenge(Ok(challenge.clone()));
+        service.get_challenge(&id).await.unwrap();
+        service.submit_commit_proofs(&id, proofs.clone()).await.unwrap();
+
+        let calls = mock.calls();
+        assert_eq!(calls.len(), 5);
+        assert_eq!(calls[4], MockCall::VerifyCommitAndAccProofs { id: id.clone(), commit_proof: proofs.clone(), challenge: challenge.clone() });
+
+        // Without verdicts proofs are not even passed on
+        mock.set_can_verify(false);
+        service.submit_commits(&id, make_commits(7, 3, 1).split_off(2)).await.unwrap();
+        mock.push_challenge(Ok(Challenge::from_flat(&[vec![3, 3903, 17, 49, 28, 8, 15, 64, 29]]).unwrap()));
+        service.get_challenge(&id).await.unwrap();
+        let result = service.submit_commit_proofs(&id, proofs).await;
+        assert!(matches!(result, Err(ServiceError::Unsupported(_))), "{:?}", result);
+        assert_eq!(mock.calls().len(), 6);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_panic_message() {
+        use std::sync::Arc;
+        use crate::{async_ffi::{AsyncCallError, AsyncPoisLibrary, OwnedCommonParam}, backend::PoisBackend};
+
+        struct Panicking;
+        impl PoisBackend for Panicking {
+            fn try_generate_commit_challenge(&self, _: &[Commit], _: &mut CommonParam, _: &ProverId) -> Result<Challenge, CallError> {
+                panic!("malformed challenge from Go")
+            }
+            fn verify_commit_and_acc_proofs(&self, _: Vec<Vec<CommitProof>>, _: Challenge, _: &mut CommonParam, _: &ProverId) -> Result<(), VerifyError> {
+                unreachable!()
+            }
+        }
+
+        let lib = AsyncPoisLibrary::with_backend(Arc::new(Panicking), 1);
+        let common_params = Arc::new(OwnedCommonParam::new(mock_rsa_key(), 7, 512, 64));
+        let result = lib.generate_commit_challenge(vec![], common_params, "panicking miner".into()).await;
+        assert!(matches!(&result, Err(AsyncCallError::Panicked(message)) if message == "malformed challenge from Go"), "{:?}", result);
+    }
+
+    #[cfg(feature = "rpc")]
+    #[tokio::test]
+    async fn test_verifier_rpc() {
+        use std::sync::Arc;
+        use jsonrpsee::{core::client::{ClientT, Error}, http_client::HttpClientBuilder, rpc_params};
+        use crate::{
+            async_ffi::OwnedCommonParam, backend::MockBackend,
+            rpc::{serve, INVALID_STATE, PROOF_REJECTED, UNKNOWN_MINER, UNSUPPORTED}, service::VerifierService, types::SpaceProof,
+        };
+
+        let params = PoisParams::devnet();
+        let store = Arc::new(MemoryStore::new());
+        let service = VerifierService::new(
+            PoisLibrary::load_default(),
+            OwnedCommonParam::from_params(&params, rsa_keygen(params.key_bits)),
+        )
+        .with_store(store.clone())
+        .unwrap();
+        let (addr, handle) = serve(Arc::new(service), "127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let client = HttpClientBuilder::default().build(format!("http://{}", addr)).unwrap();
+        let code = |result: Result<(), Error>| match result {
+            Err(Error::Call(e)) => e.code(),
+            other => panic!("expected an error response, got {:?}", other),
+        };
+
+        let id = ProverId::from("rpc miner");
+        let space_proof = SpaceProof { left: 1, right: 4, proofs: vec![], roots: vec![], wit_chains: vec![] };
+        let commits = make_commits(params.k, 4, 11);
+        assert_eq!(code(client.request("pois_submit_commits", rpc_params![&id, &commits]).await), UNKNOWN_MINER);
+        client.request::<(), _>("pois_register", rpc_params![&id]).await.unwrap();
+        assert_eq!(code(client.request("pois_register", rpc_params![&id]).await), INVALID_STATE);
+
+        // The prebuilt library reports no verdict, so proofs are not taken
+        client.request::<(), _>("pois_submit_commits", rpc_params![&id, &commits]).await.unwrap();
+        let chal: Vec<Vec<i64>> = client.request("pois_get_challenge", rpc_params![&id]).await.unwrap();
+        assert_eq!(chal.len(), 4);
+        assert_eq!(client.request::<Vec<Vec<i64>>, _>("pois_get_challenge", rpc_params![&id]).await.unwrap(), chal);
+        assert_eq!(code(client.request("pois_verify_space", rpc_params![&id, &space_proof]).await), INVALID_STATE);
+        let proofs = shaped_proofs(&Challenge::from_flat(&chal).unwrap(), params.n, params.d);
+        assert_eq!(code(client.request("pois_submit_proof", rpc_params![&id, &proofs]).await), UNSUPPORTED);
+        assert_eq!(store.get(&id).unwrap().unwrap().commits, commits);
+        handle.stop().unwrap();
+
+        // Verdicts scripted with a mock
+        let mock = Arc::new(MockBackend::new());
+        let service = VerifierService::with_backend(mock.clone(), OwnedCommonParam::new(mock_rsa_key(), params.k, params.n, params.d))
+            .with_store(store.clone())
+            .unwrap();
+        let (addr, handle) = serve(Arc::new(service), "127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let client = HttpClientBuilder::default().build(format!("http://{}", addr)).unwrap();
+        let challenge = Challenge::from_flat(&chal).unwrap();
+        mock.push_challenge(Ok(challenge.clone()));
+        mock.push_challenge(Ok(challenge.clone()));
+        mock.push_verdict(Err(VerifyError::Failed("scripted".into())));
+
+        // Rejected proofs, the commits have to be submitted again
+        let id = ProverId::from("rpc mock miner");
+        client.request::<(), _>("pois_register", rpc_params![&id]).await.unwrap();
+        client.request::<(), _>("pois_submit_commits", rpc_params![&id, &commits]).await.unwrap();
+        client.request::<Vec<Vec<i64>>, _>("pois_get_challenge", rpc_params![&id]).await.unwrap();
+        assert_eq!(code(client.request("pois_submit_proof", rpc_params![&id, &proofs]).await), PROOF_REJECTED);
+        assert_eq!(store.get(&id).unwrap().unwrap().commits, vec![]);
+
+        client.request::<(), _>("pois_submit_commits", rpc_params![&id, &commits]).await.unwrap();
+        client.request::<Vec<Vec<i64>>, _>("pois_get_challenge", rpc_params![&id]).await.unwrap();
+        client.request::<(), _>("pois_submit_proof", rpc_params![&id, &proofs]).await.unwrap();
+        assert_eq!(store.get(&id).unwrap().unwrap().count, 4);
+        assert_eq!(code(client.request("pois_verify_space", rpc_params![&id, &space_proof]).await), UNSUPPORTED);
+
+        handle.stop().unwrap();
+    }
+
+    #[cfg(feature = "grpc")]
+    #[tokio::test]
+    async fn test_verifier_grpc() {
+        use std::sync::Arc;
+        use tonic::{transport::{server::TcpIncoming, Server}, Code};
+        use crate::{
+            async_ffi::OwnedCommonParam,
+            grpc::{proto, proto::verifier_client::VerifierClient, server},
+            service::VerifierService,
+            types::{AccWitnessNode, DeletionProof},
+        };
+
+        let params = PoisParams::devnet();
+        let service = VerifierService::new(
+            PoisLibrary::load_default(),
+            OwnedCommonParam::from_params(&params, rsa_keygen(params.key_bits)),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = TcpIncoming::from_listener(listener, true, None).unwrap();
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let serving = tokio::spawn(
+            Server::builder()
+                .add_service(server(Arc::new(service)))
+                .serve_with_incoming_shutdown(incoming, async { stopped.await.unwrap_or(()) }),
+        );
+        let mut client = VerifierClient::connect(format!("http://{}", addr)).await.unwrap();
+
+        let id = b"grpc miner".to_vec();
+        let commits: Vec<proto::Commit> = make_commits(params.k, 4, 21).into_iter().map(Into::into).collect();
+        let status = client
+            .submit_commits(proto::SubmitCommitsRequest { prover_id: id.clone(), commits: commits.clone() })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+
+        client.register(proto::RegisterRequest { prover_id: id.clone() }).await.unwrap();
+        client.submit_commits(proto::SubmitCommitsRequest { prover_id: id.clone(), commits }).await.unwrap();
+        let chal = client.get_challenge(proto::GetChallengeRequest { prover_id: id.clone() }).await.unwrap().into_inner();
+        let chal = Challenge::from(chal);
+        assert_eq!(chal.0.len(), 4);
+        let files = shaped_proofs(&chal, params.n, params.d)
+            .into_iter()
+            .map(|proofs| proto::FileCommitProofs { proofs: proofs.into_iter().map(Into::into).collect() })
+            .collect();
+        // The prebuilt library reports no verdict, so proofs are not taken
+        let status = client
+            .submit_commit_proofs(proto::SubmitCommitProofsRequest { prover_id: id.clone(), files })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unimplemented);
+
+        // Proofs survive the conversion, but Go can not verify them yet
+        let wit = AccWitnessNode { elem: vec![1], wit: vec![2], acc: Some(Box::new(AccWitnessNode { elem: vec![3], wit: vec![4], acc: None })) };
+        let deletion = DeletionProof { roots: vec![vec![5; 64]], wit_chain: Some(wit.clone()), acc_path: vec![vec![6; 256]] };
+        assert_eq!(DeletionProof::from(proto::DeletionProof::from(deletion.clone())), deletion);
+        let status = client
+            .verify_deletion(proto::VerifyDeletionRequest { prover_id: id.clone(), proof: Some(deletion.into()) })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+        let status = client.verify_space(proto::VerifySpaceRequest { prover_id: id, proof: None }).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        stop.send(()).unwrap();
+        serving.await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_challenge_flat_conversion() {
+        let flat = vec![vec![1, 3903, 17, 49, 28, 8, 15, 64, 29], vec![2, 3818]];
+        let challenge = Challenge::from_flat(&flat).unwrap();
+
+        assert_eq!(challenge.0[0], CommitChallenge {
+            file_index: 1,
+            layer_selector: 3903,
+            node_indices: vec![17, 49, 28, 8, 15, 64, 29],
+        });
+        assert!(challenge.0[1].node_indices.is_empty());
+        assert_eq!(challenge.to_flat(), flat);
+        assert_eq!(Challenge::try_from(flat).unwrap(), challenge);
+
+        let err = Challenge::from_flat(&[vec![1, 2, 3], vec![4]]).unwrap_err();
+        assert_eq!((err.position, err.length), (1, 1));
+        assert!(CommitChallenge::from_flat(&[]).is_err());
+    }
+
+    #[test]
+    fn test_prover_id() {
+        let id = ProverId::from("test miner id");
+        assert_eq!(id.as_bytes(), b"test miner id");
+        assert_eq!(ProverId::from([7u8; 32]).as_bytes(), &[7u8; 32]);
+        // Not required to be UTF-8 or free of NUL bytes
+        assert_eq!(ProverId::new(vec![0xff, 0, 1]).into_bytes(), vec![0xff, 0, 1]);
+    }
+
+    #[test]
+    fn test_json_encoding() {
+        let key = rsa_keygen(512);
+        let json = serde_json::to_string(&key).unwrap();
+        assert!(json.contains(&format!("\"n\":\"{}\"", key.n)));
+        let decoded: RsaKey = serde_json::from_str(&json).unwrap();
+        assert_eq!((decoded.n, decoded.g), (key.n, key.g));
+        assert!(serde_json::from_str::<RsaKey>(r#"{"n":"12x","g":"4"}"#).is_err());
+
+        let challenge = Challenge::from_flat(&[vec![1, 3903, 17, 49, 28, 8, 15, 64, 29]]).unwrap();
+        let proofs = shaped_proofs(&challenge, 512, 64);
+        let json = serde_json::to_string(&proofs).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<Vec<CommitProof>>>(&json).unwrap(), proofs);
+    }
+
+    #[test]
+    fn test_params_presets() {
+        assert_eq!(PoisParams::preset("devnet").unwrap(), PoisParams::devnet());
+        assert_eq!(PoisParams::preset("testnet").unwrap(), PoisParams::testnet());
+        assert_eq!(PoisParams::preset("mainnet").unwrap(), PoisParams::mainnet());
+        assert!(matches!(PoisParams::preset("localnet"), Err(ParamsError::UnknownPreset(_))));
+    }
+
+    #[test]
+    fn test_params_from_toml() {
+        let params = PoisParams::from_toml_str("preset = \"mainnet\"\nn = 2048\n").unwrap();
+        assert_eq!(params, PoisParams { n: 2048, ..PoisParams::mainnet() });
+
+        let params = PoisParams::from_toml_str(
+            "k = 8\nn = 1024\nd = 32\nkey_bits = 3072\nspace_challenges = 8\n",
+        ).unwrap();
+        assert_eq!(params, PoisParams { k: 8, n: 1024, d: 32, key_bits: 3072, space_challenges: 8 });
+
+        assert!(matches!(PoisParams::from_toml_str("k = 8\n"), Err(ParamsError::MissingField("n"))));
+        assert!(matches!(PoisParams::from_toml_str("preset = \"mainnet\"\nm = 1\n"), Err(ParamsError::Parse(_))));
+    }
+
+    #[cfg(not(feature = "static"))]
+    #[test]
+    fn test_library_loading() {
+        use crate::loader::{check_version, library_candidates, LoadError, PoisVersion, VersionPolicy, BINDINGS_VERSION};
+        use std::path::Path;
+
+        // Only debug builds fall back on the library they were built with
+        let fallback = library_candidates().iter().any(|path| path == Path::new(DEFAULT_LIBRARY_PATH));
+        assert_eq!(fallback, cfg!(debug_assertions));
+
+        // The prebuilt library predates PoisVersion, it is loaded anyway
+        // unless the version is required
+        let lib = PoisLibrary::load_default();
+        assert_eq!(lib.version(), None);
+        assert!(PoisLibrary::open(DEFAULT_LIBRARY_PATH).is_ok());
+        let e = PoisLibrary::open_with(DEFAULT_LIBRARY_PATH, VersionPolicy::Strict).err().unwrap();
+        assert!(matches!(e, LoadError::NoVersion { .. }), "{}", e);
+        if cfg!(debug_assertions) {
+            assert!(PoisLibrary::discover().is_ok());
+        }
+        assert!(matches!(PoisLibrary::open("/nonexistent/libpois.so"), Err(LoadError::Open { .. })));
+
+        let path = Path::new("libpois.so");
+        assert!(check_version(path, None, VersionPolicy::Lenient).is_ok());
+        assert!(matches!(check_version(path, None, VersionPolicy::Strict), Err(LoadError::NoVersion { .. })));
+        assert!(check_version(path, Some(BINDINGS_VERSION), VersionPolicy::Strict).is_ok());
+        let older = PoisVersion { abi: BINDINGS_VERSION.abi - 1, ..BINDINGS_VERSION };
+        let newer = PoisVersion { protocol: BINDINGS_VERSION.protocol + 1, ..BINDINGS_VERSION };
+        let e = check_version(path, Some(older), VersionPolicy::Lenient).unwrap_err();
+        assert!(e.to_string().contains("older than these bindings"), "{}", e);
+        let e = check_version(path, Some(newer), VersionPolicy::Lenient).unwrap_err();
+        assert!(e.to_string().contains("newer than these bindings"), "{}", e);
+    }
+
+    #[test]
+    fn test_go_diagnostics() {
+        use crate::diagnostics::{forward_log, go_log_level, take_error, GoError, GoErrorKind, GO_ERROR_PANIC, GO_LOG_TARGET};
+        use crate::ffi::go_lock;
+        use std::{os::raw::{c_char, c_int}, ptr, sync::Mutex};
+
+        let levels: Vec<_> = [-8, -4, 0, 2, 4, 8, 12].into_iter().map(go_log_level).collect();
+        assert_eq!(levels, [log::Level::Trace, log::Level::Debug, log::Level::Info, log::Level::Info,
+            log::Level::Warn, log::Level::Error, log::Level::Error]);
+
+        let e = GoError::from_code(GO_ERROR_PANIC, "index out of range".to_string());
+        assert_eq!(e.kind, GoErrorKind::Panic);
+        assert_eq!(e.to_string(), "Go panicked: index out of range");
+        assert_eq!(GoError::from_code(7, String::new()).kind, GoErrorKind::Other(7));
+
+        // What Go would send to PoisSetLogger's callback
+        struct Capture(Mutex<Vec<(log::Level, String)>>);
+        impl log::Log for Capture {
+            fn enabled(&self, metadata: &log::Metadata) -> bool {
+                metadata.target() == GO_LOG_TARGET
+            }
+            fn log(&self, record: &log::Record) {
+                if self.enabled(record.metadata()) {
+                    self.0.lock().unwrap().push((record.level(), record.args().to_string()));
+                }
+            }
+            fn flush(&self) {}
+        }
+        static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+        log::set_logger(&CAPTURE).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+        let line = b"bad file number\n";
+        unsafe {
+            forward_log(4, line.as_ptr() as *const c_char, line.len() as c_int);
+            forward_log(0, ptr::null(), 3);
+            forward_log(8, line.as_ptr() as *const c_char, -1);
+        }
+        assert_eq!(*CAPTURE.0.lock().unwrap(), [
+            (log::Level::Warn, "bad file number".to_string()),
+            (log::Level::Info, String::new()),
+            (log::Level::Error, String::new()),
+        ]);
+
+        // The prebuilt library has neither export, so no errors to take
+        let lib = PoisLibrary::load_default();
+        assert!(lib.exports().set_logger.is_none());
+        assert!(lib.exports().last_error.is_none());
+        let _lock = go_lock();
+        assert_eq!(take_error(&lib), None);
+    }
+
+    #[test]
+    fn test_commit_c_array_view() {
+        let commits = vec![
+            Commit { file_index: 1, roots: vec![vec![1, 2, 3], vec![], vec![4]] },
+            Commit { file_index: 2, roots: vec![] },
+        ];
+
+        let commits_c = rust_commit_array_to_commit_c_array(&commits);
+        let view = commits_c.view();
+
+        assert_eq!(view.len(), 2);
+        let first = view.get(0).unwrap();
+        assert_eq!(first.file_index(), 1);
+        assert_eq!(first.roots().iter().collect::<Vec<_>>(), vec![&[1u8, 2, 3][..], &[], &[4]]);
+        assert!(view.get(1).unwrap().roots().is_empty());
+        assert!(view.get(2).is_none());
+        assert_eq!(view.to_vec(), commits);
+    }
+
+    #[test]
+    fn test_i64_matrix_view() {
+        let rows: Vec<Vec<i64>> = vec![vec![1, 3903, 17], vec![], vec![2]];
+        let mut ptrs: Vec<*mut i64> = rows.iter().map(|row| row.as_ptr() as *mut i64).collect();
+        let lengths: Vec<i32> = rows.iter().map(|row| row.len() as i32).collect();
+
+        let view = unsafe { I64MatrixView::from_raw(ptrs.as_mut_ptr(), lengths.as_ptr(), rows.len() as i32) }.unwrap();
+        assert_eq!(view.get(0), Some(&[1i64, 3903, 17][..]));
+        assert_eq!(view.to_vec(), rows);
+
+        let empty = unsafe { I64MatrixView::from_raw(std::ptr::null(), std::ptr::null(), 0) }.unwrap();
+        assert!(empty.is_empty());
+        assert!(empty.to_vec().is_empty());
+    }
+
+    #[test]
+    fn test_malformed_i64_array_of_array() {
+        let row = [1i64, 2, 3];
+        let mut rows = [row.as_ptr() as *mut i64];
+        let null_rows = [std::ptr::null_mut::<i64>()];
+        let null = std::ptr::null_mut();
+
+        let convert = |main_array: *const *mut i64, lengths: &[i32], length: i32| unsafe {
+            c_ptr_to_i64_array_of_array(main_array as *mut *mut i64, lengths.as_ptr(), length)
+        };
+
+        assert_eq!(convert(rows.as_mut_ptr(), &[3], 1).unwrap(), vec![vec![1, 2, 3]]);
+        assert_eq!(convert(null, &[], 0).unwrap(), Vec::<Vec<i64>>::new());
+        assert_eq!(convert(null_rows.as_ptr(), &[0], 1).unwrap(), vec![Vec::<i64>::new()]);
+
+        assert_eq!(convert(null, &[], 2), Err(MarshalError::NullPointer { field: "main_array" }));
+        assert_eq!(convert(rows.as_mut_ptr(), &[3], -1), Err(MarshalError::NegativeLength { field: "main_array", length: -1 }));
+        assert_eq!(convert(rows.as_mut_ptr(), &[-3], 1), Err(MarshalError::NegativeLength { field: "sub_array", length: -3 }));
+        assert_eq!(convert(null_rows.as_ptr(), &[3], 1), Err(MarshalError::NullPointer { field: "sub_array" }));
+        assert_eq!(
+            unsafe { c_ptr_to_i64_array_of_array(rows.as_mut_ptr(), std::ptr::null(), 1) },
+            Err(MarshalError::NullPointer { field: "sub_array_lengths" })
+        );
+
+    }
+
+    #[test]
+    fn test_arr_of_arr_buf() {
+        let rows: Vec<Vec<u8>> = vec![vec![1, 2], vec![], vec![3]];
+        let buf = ArrOfArrBuf::new(rows.clone());
+
+        let raw = buf.as_raw();
+        assert_eq!(raw.length, 3);
+        assert_eq!(unsafe { raw.view() }.unwrap().to_vec(), rows);
+        assert_eq!(buf.view().to_vec(), rows);
+
+        let empty = ArrOfArrBuf::<u8>::new(vec![]);
+        assert!(empty.as_raw().main_array.is_null());
+        assert!(unsafe { ArrOfArrView::from_raw(empty.as_raw().main_array, empty.as_raw().sub_array_lengths, 0) }.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_malformed_commit_c_array() {
+        let root = [7u8, 8];
+        let mut roots = [root.as_ptr() as *mut u8];
+        let mut lengths = [2i32];
+        let commit = |roots: *mut *mut u8, roots_length: i32, sub_roots_lengths: *mut i32| CommitC {
+            file_index: 1,
+            roots,
+            roots_length,
+            sub_roots_lengths,
+        };
+        let convert = |commits: &[CommitC], length: i64| unsafe {
+            commit_c_array_to_rust_commit_array(commits.as_ptr(), length)
+        };
+
+        let good = [commit(roots.as_mut_ptr(), 1, lengths.as_mut_ptr())];
+        assert_eq!(convert(&good, 1).unwrap(), vec![Commit { file_index: 1, roots: vec![vec![7, 8]] }]);
+        assert_eq!(unsafe { commit_c_array_to_rust_commit_array(std::ptr::null(), 0) }.unwrap(), vec![]);
+
+        assert_eq!(
+            unsafe { commit_c_array_to_rust_commit_array(std::ptr::null(), 1) },
+            Err(MarshalError::NullPointer { field: "commits" })
+        );
+        assert_eq!(convert(&good, -1), Err(MarshalError::NegativeLength { field: "commits", length: -1 }));
+        assert_eq!(convert(&good, i64::MAX), Err(MarshalError::TooLarge { field: "commits" }));
+
+        let null_roots = [commit(std::ptr::null_mut(), 1, lengths.as_mut_ptr())];
+        assert_eq!(convert(&null_roots, 1), Err(MarshalError::NullPointer { field: "roots" }));
+
+        let negative_roots = [commit(roots.as_mut_ptr(), -1, lengths.as_mut_ptr())];
+        assert_eq!(convert(&negative_roots, 1), Err(MarshalError::NegativeLength { field: "roots", length: -1 }));
+
+        let mut negative_lengths = [-2i32];
+        let negative_root = [commit(roots.as_mut_ptr(), 1, negative_lengths.as_mut_ptr())];
+        assert_eq!(convert(&negative_root, 1), Err(MarshalError::NegativeLength { field: "root", length: -2 }));
+
+        let mut null_root = [std::ptr::null_mut::<u8>()];
+        let null_root = [commit(null_root.as_mut_ptr(), 1, lengths.as_mut_ptr())];
+        assert_eq!(convert(&null_root, 1), Err(MarshalError::NullPointer { field: "root" }));
+    }
+
+    // Round trips through the C types, with empty arrays and sub arrays,
+    // which are passed as null pointers. They do not call Go, so they also
+    // run under Miri, in CI (.github/workflows/miri.yml):
+    //
+    //     cargo +nightly miri test --lib round_trip
+    mod round_trip {
+        use proptest::{collection::vec, option, prelude::*};
+
+        use crate::{
+            types::{Commit, CommitProof, MhtProof},
+            utils::{
+                c_ptr_to_i64_array_of_array, commit_c_array_to_rust_commit_array,
+                commit_proof_c_array_to_rust_commit_proof_array, rust_commit_array_to_commit_c_array,
+                rust_commit_proof_array_to_commit_proof_c_array,
+            },
+            views::{ArrOfArrBuf, MhtProofCView},
+        };
+
+        // Miri is slow and cannot write the failure files
+        fn config() -> ProptestConfig {
+            if cfg!(miri) {
+                ProptestConfig { cases: 8, failure_persistence: None, ..ProptestConfig::default() }
+            } else {
+                ProptestConfig::default()
+            }
+        }
+
+        fn rows<T: Arbitrary>() -> impl Strategy<Value = Vec<Vec<T>>> {
+            vec(vec(any::<T>(), 0..6), 0..5)
+        }
+
+        fn mht_proof() -> impl Strategy<Value = MhtProof> {
+            (any::<i32>(), vec(any::<u8>(), 0..6), rows::<u8>(), vec(any::<u8>(), 0..6))
+                .prop_map(|(index, label, paths, locs)| MhtProof { index, label, paths, locs })
+        }
+
+        fn commit_proofs() -> impl Strategy<Value = Vec<Vec<CommitProof>>> {
+            let proof = (option::of(mht_proof()), vec(mht_proof(), 0..4))
+                .prop_map(|(node, parents)| CommitProof { node, parents });
+            vec(vec(proof, 0..3), 0..3)
+        }
+
+        proptest! {
+            #![proptest_config(config())]
+
+            #[test]
+            fn test_round_trip_commits(
+                commits in vec((any::<i64>(), rows::<u8>()), 0..4)
+                    .prop_map(|c| c.into_iter().map(|(file_index, roots)| Commit { file_index, roots }).collect::<Vec<_>>())
+            ) {
+                let mut commits_c = rust_commit_array_to_commit_c_array(&commits);
+                let back = unsafe { commit_c_array_to_rust_commit_array(commits_c.as_mut_ptr(), commits_c.len() as i64) };
+                prop_assert_eq!(back, Ok(commits));
+            }
+
+            #[test]
+            fn test_round_trip_i64_arr_of_arr(rows in rows::<i64>()) {
+                let buf = ArrOfArrBuf::new(rows.clone());
+                let raw = buf.as_raw();
+                prop_assert_eq!(raw.main_array.is_null(), rows.is_empty());
+                for (i, row) in rows.iter().enumerate() {
+                    prop_assert_eq!(unsafe { *raw.main_array.add(i) }.is_null(), row.is_empty());
+                }
+                let back = unsafe { c_ptr_to_i64_array_of_array(raw.main_array, raw.sub_array_lengths, raw.length) };
+                prop_assert_eq!(back, Ok(rows));
+            }
+
+            #[test]
+            fn test_round_trip_mht_proof(proof in mht_proof()) {
+                let proofs = vec![vec![CommitProof { node: Some(proof.clone()), parents: vec![] }]];
+                let mut proofs_c = rust_commit_proof_array_to_commit_proof_c_array(&proofs);
+                let node = unsafe { &*(**proofs_c.as_mut_ptr()).node };
+                prop_assert_eq!(node.label.is_null(), proof.label.is_empty());
+                prop_assert_eq!(node.locs.is_null(), proof.locs.is_empty());
+                prop_assert_eq!(node.paths.is_null(), proof.paths.is_empty());
+                let back = unsafe { MhtProofCView::from_raw(node) }.map(|view| view.to_proof());
+                prop_assert_eq!(back, Ok(proof));
+            }
+
+            #[test]
+            fn test_round_trip_commit_proofs(proofs in commit_proofs()) {
+                let mut proofs_c = rust_commit_proof_array_to_commit_proof_c_array(&proofs);
+                let lengths: Vec<i64> = proofs.iter().map(|file| file.len() as i64).collect();
+                let back = unsafe { commit_proof_c_array_to_rust_commit_proof_array(proofs_c.as_mut_ptr(), &lengths) };
+                prop_assert_eq!(back, Ok(proofs.clone()));
+                prop_assert_eq!(proofs_c.view().to_vec(), proofs);
+            }
+        }
     }
 
+    #[cfg(not(feature = "static"))]
     #[test]
     fn test_example_functions() {
-        call_return_an_array()
+        crate::examples::call_return_an_array()
     }
 
 }
@@ -0,0 +1,210 @@
+use std::{
+    cmp::Ordering,
+    env, fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::ffi::PoisLibrary;
+
+// Finding the Go library and checking it matches these bindings.
+//
+// PoisLibrary::discover looks, in order, at
+//   - the path in CESS_POIS_LIB
+//   - libpois.so (the platform's name for it) next to the executable
+//   - libpois.so in the system paths, as searched by the dynamic loader
+//   - in debug builds only, DEFAULT_LIBRARY_PATH, the library this crate
+//     was built with, only right on the machine it was built on. Release
+//     builds are distributed, so they never look there.
+// and takes the first one that loads. PoisLibrary::load_default looks
+// the same way. Before it is used, the library's
+// PoisVersion export must report the ABI and protocol versions below.
+// The Go side exports it as
+//
+//     //export PoisVersion
+//     func PoisVersion() (C.int, C.int) // ABI, protocol
+//
+// Libraries built before the handshake, the prebuilt cgo/main.so among
+// them, do not have it. With VersionPolicy::Lenient, what open and discover
+// use, they are loaded with a warning, as there is no telling whether they
+// match. With VersionPolicy::Strict, see open_with and discover_with, they
+// are refused. PoisLibrary::load loads any library unchecked.
+
+pub const LIBRARY_ENV: &str = "CESS_POIS_LIB";
+
+// Versions of the Go library these bindings are written for. The ABI
+// version covers the exported functions and the C types they take, the
+// protocol version what the verifier computes, e.g. the challenges.
+pub const BINDINGS_VERSION: PoisVersion = PoisVersion { abi: 1, protocol: 1 };
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoisVersion {
+    pub abi: u32,
+    pub protocol: u32,
+}
+
+impl fmt::Display for PoisVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ABI {} protocol {}", self.abi, self.protocol)
+    }
+}
+
+// What to do with a library that does not export PoisVersion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VersionPolicy {
+    // Load it with a warning
+    #[default]
+    Lenient,
+    // Refuse it
+    Strict,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    // Every candidate, with why it could not be loaded
+    NotFound(Vec<(PathBuf, String)>),
+    Open { path: PathBuf, message: String },
+    Incompatible { path: PathBuf, found: PoisVersion },
+    NoVersion { path: PathBuf },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::NotFound(tried) => {
+                write!(f, "no Go library found, set {} to its path. Tried:", LIBRARY_ENV)?;
+                for (path, message) in tried {
+                    write!(f, "\n  {}: {}", path.display(), message)?;
+                }
+                Ok(())
+            }
+            LoadError::Open { path, message } => write!(f, "failed to load {}: {}", path.display(), message),
+            LoadError::Incompatible { path, found } => {
+                let age = match (found.abi, found.protocol).cmp(&(BINDINGS_VERSION.abi, BINDINGS_VERSION.protocol)) {
+                    Ordering::Less => "older than",
+                    _ => "newer than",
+                };
+                write!(
+                    f,
+                    "{} is {} these bindings: it has {}, the bindings {}",
+                    path.display(),
+                    age,
+                    found,
+                    BINDINGS_VERSION
+                )
+            }
+            LoadError::NoVersion { path } => write!(
+                f,
+                "{} does not export PoisVersion, there is no telling whether it matches these bindings ({})",
+                path.display(),
+                BINDINGS_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+// Where discover looks, in order
+pub fn library_candidates() -> Vec<PathBuf> {
+    let name = PathBuf::from(libloading::library_filename("pois"));
+    let mut candidates = Vec::new();
+    if let Some(path) = env::var_os(LIBRARY_ENV) {
+        candidates.push(PathBuf::from(path));
+    }
+    if let Some(dir) = env::current_exe().ok().as_deref().and_then(Path::parent) {
+        candidates.push(dir.join(&name));
+    }
+    // A bare file name makes the dynamic loader search the system paths
+    candidates.push(name);
+    #[cfg(debug_assertions)]
+    candidates.push(PathBuf::from(crate::ffi::DEFAULT_LIBRARY_PATH));
+    candidates
+}
+
+// found is what PoisVersion reported, None if the library lacks it
+pub fn check_version(path: &Path, found: Option<PoisVersion>, policy: VersionPolicy) -> Result<(), LoadError> {
+    match found {
+        None if policy == VersionPolicy::Strict => Err(LoadError::NoVersion { path: path.to_path_buf() }),
+        None => {
+            log::warn!(
+                "{} does not export PoisVersion, it may not match these bindings ({})",
+                path.display(),
+                BINDINGS_VERSION
+            );
+            Ok(())
+        }
+        Some(found) if found != BINDINGS_VERSION => Err(LoadError::Incompatible {
+            path: path.to_path_buf(),
+            found,
+        }),
+        Some(_) => Ok(()),
+    }
+}
+
+// The first of library_candidates the dynamic loader opens
+#[cfg(not(feature = "static"))]
+pub(crate) fn open_candidate() -> Result<(PathBuf, libloading::Library), LoadError> {
+    let mut tried = Vec::new();
+    for path in library_candidates() {
+        match unsafe { libloading::Library::new(&path) } {
+            Ok(lib) => return Ok((path, lib)),
+            Err(e) => tried.push((path, e.to_string())),
+        }
+    }
+    Err(LoadError::NotFound(tried))
+}
+
+#[cfg(not(feature = "static"))]
+impl PoisLibrary {
+    // Load the library at path, checking its version
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        PoisLibrary::open_with(path, VersionPolicy::Lenient)
+    }
+
+    pub fn open_with(path: impl AsRef<Path>, policy: VersionPolicy) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+        let lib = unsafe { libloading::Library::new(path) }.map_err(|e| LoadError::Open {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })?;
+        let lib = PoisLibrary::from_library(lib);
+        check_version(path, lib.version(), policy)?;
+        Ok(lib)
+    }
+
+    // The first of library_candidates that loads. An incompatible
+    // library is an error rather than a reason to look further.
+    pub fn discover() -> Result<Self, LoadError> {
+        PoisLibrary::discover_with(VersionPolicy::Lenient)
+    }
+
+    pub fn discover_with(policy: VersionPolicy) -> Result<Self, LoadError> {
+        let (path, lib) = open_candidate()?;
+        let lib = PoisLibrary::from_library(lib);
+        check_version(&path, lib.version(), policy)?;
+        Ok(lib)
+    }
+}
+
+// Linked statically there is nothing to look for. The version is only
+// reported when the archive exports PoisVersion, see link_archive in build.rs.
+#[cfg(feature = "static")]
+impl PoisLibrary {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        PoisLibrary::open_with(path, VersionPolicy::Lenient)
+    }
+
+    pub fn open_with(_path: impl AsRef<Path>, policy: VersionPolicy) -> Result<Self, LoadError> {
+        let lib = PoisLibrary::load_default();
+        check_version(Path::new("libpois.a"), lib.version(), policy)?;
+        Ok(lib)
+    }
+
+    pub fn discover() -> Result<Self, LoadError> {
+        PoisLibrary::discover_with(VersionPolicy::Lenient)
+    }
+
+    pub fn discover_with(policy: VersionPolicy) -> Result<Self, LoadError> {
+        PoisLibrary::open_with("libpois.a", policy)
+    }
+}
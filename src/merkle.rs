@@ -0,0 +1,110 @@
+use std::fmt;
+
+use sha2::{Digest, Sha512};
+
+use crate::types::MhtProof;
+
+// Merkle paths of the labels in a commit proof, checked natively.
+//
+// A MhtProof proves its label is a leaf of the tree whose root was
+// committed. paths[i] is the sibling of the node at level i, counted from
+// the leaf, and locs[i] where that node sits: 0 when it is the left child,
+// so the parent is the SHA-512 of node || sibling, and 1 when it is the
+// right child, the parent being the SHA-512 of sibling || node. The label
+// is the leaf as is. Go verifies the paths as well and has the last word.
+//
+// Proofs come from miners, so the shape of a path is checked before
+// anything is copied or hashed: as many locs as path nodes, no deeper than
+// MAX_PATH_DEPTH, and a label and path nodes of HASH_SIZE bytes, which
+// bounds what a hostile proof costs to copy, marshal and hash.
+
+// cess_pois/expanders.HashSize, 64 in the prebuilt cgo/main.so: labels
+// and tree nodes are SHA-512 hashes
+pub const HASH_SIZE: usize = 64;
+
+// Enough for a binary tree over any number of nodes an i64 can index
+pub const MAX_PATH_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    LocsMismatch { paths: usize, locs: usize },
+    TooDeep { depth: usize },
+    LabelSize { size: usize },
+    NodeSize { level: usize, size: usize },
+    InvalidLoc { level: usize, loc: u8 },
+    RootMismatch,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::LocsMismatch { paths, locs } => {
+                write!(f, "{} path nodes but {} locs", paths, locs)
+            }
+            PathError::TooDeep { depth } => {
+                write!(f, "path of {} nodes, at most {} allowed", depth, MAX_PATH_DEPTH)
+            }
+            PathError::LabelSize { size } => write!(f, "label has {} bytes, expected {}", size, HASH_SIZE),
+            PathError::NodeSize { level, size } => {
+                write!(f, "path node {} has {} bytes, expected {}", level, size, HASH_SIZE)
+            }
+            PathError::InvalidLoc { level, loc } => write!(f, "loc {} is {}, expected 0 or 1", level, loc),
+            PathError::RootMismatch => write!(f, "path does not lead to the committed root"),
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
+
+pub fn check_path_shape(proof: &MhtProof) -> Result<(), PathError> {
+    if proof.paths.len() != proof.locs.len() {
+        return Err(PathError::LocsMismatch {
+            paths: proof.paths.len(),
+            locs: proof.locs.len(),
+        });
+    }
+    if proof.paths.len() > MAX_PATH_DEPTH {
+        return Err(PathError::TooDeep { depth: proof.paths.len() });
+    }
+    if proof.label.len() != HASH_SIZE {
+        return Err(PathError::LabelSize { size: proof.label.len() });
+    }
+    match proof.paths.iter().position(|node| node.len() != HASH_SIZE) {
+        Some(level) => Err(PathError::NodeSize {
+            level,
+            size: proof.paths[level].len(),
+        }),
+        None => Ok(()),
+    }
+}
+
+// The root the path of proof leads to, the label itself for an empty path
+pub fn path_root(proof: &MhtProof) -> Result<Vec<u8>, PathError> {
+    check_path_shape(proof)?;
+
+    let mut node = proof.label.clone();
+    let mut hash = Sha512::new();
+    for (level, (sibling, &loc)) in proof.paths.iter().zip(&proof.locs).enumerate() {
+        match loc {
+            0 => {
+                hash.update(&node);
+                hash.update(sibling);
+            }
+            1 => {
+                hash.update(sibling);
+                hash.update(&node);
+            }
+            loc => return Err(PathError::InvalidLoc { level, loc }),
+        }
+        node = hash.finalize_reset().to_vec();
+    }
+    Ok(node)
+}
+
+pub fn verify_path(root: &[u8], proof: &MhtProof) -> Result<(), PathError> {
+    if path_root(proof)? == root {
+        Ok(())
+    } else {
+        Err(PathError::RootMismatch)
+    }
+}
@@ -0,0 +1,139 @@
+use std::{fmt, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    c_types::CommonParam,
+    utils::{init_common_params, rsa_keygen},
+};
+
+// Parameters shared by the prover and the verifier.
+// k, n and d describe the expander graph, key_bits the RSA accumulator key
+// and space_challenges the number of challenges in a space proof.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PoisParams {
+    pub k: i64,
+    pub n: i64,
+    pub d: i64,
+    pub key_bits: usize,
+    pub space_challenges: i64,
+}
+
+// Where the preset values come from:
+//   - k = 7, d = 64 and a 2048 bit key: init_params in the original test of
+//     this crate, the parameters the prebuilt cgo/main.so was exercised with
+//   - n = 512 for devnet: the same init_params, n = 1024 * 1024 * 4 for
+//     testnet and mainnet: the value commented out next to it
+//   - space_challenges = 22: cess_pois/pois.SpaceChals, 22 in the .noptrdata
+//     of cgo/main.so
+// Nothing sets a testnet n of its own, so testnet runs what mainnet runs.
+impl PoisParams {
+    // Small expanders for local development and tests
+    pub fn devnet() -> Self {
+        PoisParams {
+            k: 7,
+            n: 512,
+            d: 64,
+            key_bits: 2048,
+            space_challenges: 22,
+        }
+    }
+
+    pub fn testnet() -> Self {
+        Self::mainnet()
+    }
+
+    pub fn mainnet() -> Self {
+        PoisParams {
+            k: 7,
+            n: 1024 * 1024 * 4,
+            d: 64,
+            key_bits: 2048,
+            space_challenges: 22,
+        }
+    }
+
+    pub fn preset(name: &str) -> Result<Self, ParamsError> {
+        match name {
+            "devnet" => Ok(Self::devnet()),
+            "testnet" => Ok(Self::testnet()),
+            "mainnet" => Ok(Self::mainnet()),
+            _ => Err(ParamsError::UnknownPreset(name.to_string())),
+        }
+    }
+
+    // The TOML either names a preset, sets every field,
+    // or names a preset and overrides some of its fields.
+    //
+    //     preset = "testnet"
+    //     n = 2048
+    pub fn from_toml_str(s: &str) -> Result<Self, ParamsError> {
+        let file: ParamsFile = toml::from_str(s).map_err(ParamsError::Parse)?;
+
+        let base = match &file.preset {
+            Some(name) => Some(Self::preset(name)?),
+            None => None,
+        };
+
+        let base = base.as_ref();
+        Ok(PoisParams {
+            k: pick(file.k, base.map(|p| p.k), "k")?,
+            n: pick(file.n, base.map(|p| p.n), "n")?,
+            d: pick(file.d, base.map(|p| p.d), "d")?,
+            key_bits: pick(file.key_bits, base.map(|p| p.key_bits), "key_bits")?,
+            space_challenges: pick(
+                file.space_challenges,
+                base.map(|p| p.space_challenges),
+                "space_challenges",
+            )?,
+        })
+    }
+
+    pub fn from_toml_file<P: AsRef<Path>>(path: P) -> Result<Self, ParamsError> {
+        let s = fs::read_to_string(path).map_err(ParamsError::Io)?;
+        Self::from_toml_str(&s)
+    }
+
+    // Generate a fresh RSA key of key_bits and build the CommonParam for it.
+    pub fn to_common_param(&self) -> CommonParam {
+        let rsa_key = rsa_keygen(self.key_bits);
+        init_common_params(rsa_key, self.k, self.n, self.d)
+    }
+}
+
+// A value set in the file wins over the one from the preset
+fn pick<T>(value: Option<T>, preset: Option<T>, name: &'static str) -> Result<T, ParamsError> {
+    value.or(preset).ok_or(ParamsError::MissingField(name))
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ParamsFile {
+    preset: Option<String>,
+    k: Option<i64>,
+    n: Option<i64>,
+    d: Option<i64>,
+    key_bits: Option<usize>,
+    space_challenges: Option<i64>,
+}
+
+#[derive(Debug)]
+pub enum ParamsError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    UnknownPreset(String),
+    MissingField(&'static str),
+}
+
+impl fmt::Display for ParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamsError::Io(e) => write!(f, "failed to read params file: {}", e),
+            ParamsError::Parse(e) => write!(f, "failed to parse params file: {}", e),
+            ParamsError::UnknownPreset(name) => write!(f, "unknown params preset: {}", name),
+            ParamsError::MissingField(name) => write!(f, "missing params field: {}", name),
+        }
+    }
+}
+
+impl std::error::Error for ParamsError {}
@@ -0,0 +1,92 @@
+use std::{io, net::SocketAddr, sync::Arc};
+
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    server::{Server, ServerHandle},
+    types::{error::INTERNAL_ERROR_CODE, ErrorObjectOwned},
+};
+
+use crate::{
+    service::{ServiceError, VerifierService},
+    session::SessionError,
+    types::{Commit, CommitProof, ProverId, SpaceProof},
+};
+
+// JSON-RPC API of VerifierService, served by pois-verifierd.
+//
+// Methods are in the "pois" namespace, e.g. pois_register. Challenges are
+// the flat [file_index, layer_selector, s_1 .. s_k] rows.
+
+pub const UNKNOWN_MINER: i32 = -32001;
+// The request is not allowed in the miner's state
+pub const INVALID_STATE: i32 = -32002;
+pub const PROOF_REJECTED: i32 = -32003;
+pub const UNSUPPORTED: i32 = -32004;
+
+#[rpc(server, namespace = "pois")]
+pub trait VerifierRpc {
+    #[method(name = "register")]
+    async fn register(&self, id: ProverId) -> RpcResult<()>;
+
+    #[method(name = "submit_commits")]
+    async fn submit_commits(&self, id: ProverId, commits: Vec<Commit>) -> RpcResult<()>;
+
+    #[method(name = "get_challenge")]
+    async fn get_challenge(&self, id: ProverId) -> RpcResult<Vec<Vec<i64>>>;
+
+    // Fails with UNSUPPORTED when the Go library reports no verdict
+    #[method(name = "submit_proof")]
+    async fn submit_proof(&self, id: ProverId, proofs: Vec<Vec<CommitProof>>) -> RpcResult<()>;
+
+    // Always fails with UNSUPPORTED, the Go library cannot verify space proofs
+    #[method(name = "verify_space")]
+    async fn verify_space(&self, id: ProverId, proof: SpaceProof) -> RpcResult<()>;
+}
+
+// Serve service on addr, port 0 picks a free one. Returns the bound address.
+pub async fn serve(service: Arc<VerifierService>, addr: SocketAddr) -> io::Result<(SocketAddr, ServerHandle)> {
+    let server = Server::builder().build(addr).await?;
+    let addr = server.local_addr()?;
+    Ok((addr, server.start(RpcVerifier(service).into_rpc())))
+}
+
+struct RpcVerifier(Arc<VerifierService>);
+
+#[async_trait]
+impl VerifierRpcServer for RpcVerifier {
+    async fn register(&self, id: ProverId) -> RpcResult<()> {
+        self.0.register(id).await.map_err(rpc_error)
+    }
+
+    async fn submit_commits(&self, id: ProverId, commits: Vec<Commit>) -> RpcResult<()> {
+        self.0.submit_commits(&id, commits).await.map_err(rpc_error)
+    }
+
+    async fn get_challenge(&self, id: ProverId) -> RpcResult<Vec<Vec<i64>>> {
+        self.0.get_challenge(&id).await.map(|c| c.to_flat()).map_err(rpc_error)
+    }
+
+    async fn submit_proof(&self, id: ProverId, proofs: Vec<Vec<CommitProof>>) -> RpcResult<()> {
+        self.0.submit_commit_proofs(&id, proofs).await.map_err(rpc_error)
+    }
+
+    async fn verify_space(&self, id: ProverId, proof: SpaceProof) -> RpcResult<()> {
+        self.0.verify_space(&id, &proof).map_err(rpc_error)
+    }
+}
+
+fn rpc_error(e: ServiceError) -> ErrorObjectOwned {
+    let code = match &e {
+        ServiceError::UnknownMiner => UNKNOWN_MINER,
+        ServiceError::Session(SessionError::Rejected(_) | SessionError::DeadlineExpired { .. }) => {
+            PROOF_REJECTED
+        }
+        ServiceError::AlreadyRegistered | ServiceError::CommitsRejected | ServiceError::Session(_) => {
+            INVALID_STATE
+        }
+        ServiceError::Unsupported(_) => UNSUPPORTED,
+        ServiceError::Store(_) | ServiceError::Internal(_) => INTERNAL_ERROR_CODE,
+    };
+    ErrorObjectOwned::owned(code, e.to_string(), None::<()>)
+}
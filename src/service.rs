@@ -0,0 +1,286 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use tokio::task;
+
+use crate::{
+    async_ffi::OwnedCommonParam,
+    backend::PoisBackend,
+    diagnostics::{GoError, GoErrorKind},
+    ffi::{CallError, PoisLibrary, VerifyError},
+    session::{MinerSession, MinerState, SessionError},
+    store::{rehydrate_sessions, ProverNodeRecord, StoreError, VerifierStore},
+    types::{Challenge, Commit, CommitProof, DeletionProof, ProverId, SpaceProof},
+    utils::lock,
+};
+
+// The verifier behind the network services in rpc and grpc.
+//
+// A miner registers, submits its commits, fetches the challenge for them
+// and answers it with its commit proofs before the challenge times out,
+// see MinerSession. Each miner has its own lock, never held across a call
+// into Go or the store, which run on tokio's blocking pool.
+//
+// Go is reached through a PoisBackend, with_backend takes e.g. a
+// MockBackend to test what is built on the service without Go.
+//
+// The Go library exports no space or deletion proof verification, those
+// requests only check the miner may be audited and fail with Unsupported.
+// So do commit proofs when the library reports no verdict on them, see
+// PoisLibrary::can_verify, rather than being rejected whatever they hold.
+
+#[derive(Debug)]
+pub enum ServiceError {
+    UnknownMiner,
+    AlreadyRegistered,
+    // Go returned no challenge, it logs why
+    CommitsRejected,
+    Session(SessionError),
+    Unsupported(&'static str),
+    Store(StoreError),
+    // The blocking call panicked, or Go failed
+    Internal(String),
+}
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServiceError::UnknownMiner => write!(f, "unknown miner"),
+            ServiceError::AlreadyRegistered => write!(f, "miner already registered"),
+            ServiceError::CommitsRejected => write!(f, "commits rejected by the Go verifier"),
+            ServiceError::Session(e) => write!(f, "{}", e),
+            ServiceError::Unsupported(what) => write!(f, "{} not supported by the Go library", what),
+            ServiceError::Store(e) => write!(f, "{}", e),
+            ServiceError::Internal(message) => write!(f, "internal error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ServiceError {}
+
+impl From<SessionError> for ServiceError {
+    fn from(e: SessionError) -> Self {
+        ServiceError::Session(e)
+    }
+}
+
+impl From<StoreError> for ServiceError {
+    fn from(e: StoreError) -> Self {
+        ServiceError::Store(e)
+    }
+}
+
+type SharedSession = Arc<Mutex<MinerSession>>;
+
+pub struct VerifierService {
+    lib: Arc<dyn PoisBackend>,
+    common_params: Arc<OwnedCommonParam>,
+    store: Option<Arc<dyn VerifierStore>>,
+    challenge_timeout: Duration,
+    sessions: Mutex<HashMap<ProverId, SharedSession>>,
+}
+
+impl VerifierService {
+    pub fn new(lib: PoisLibrary, common_params: OwnedCommonParam) -> Self {
+        Self::with_backend(Arc::new(lib), common_params)
+    }
+
+    pub fn with_backend(lib: Arc<dyn PoisBackend>, common_params: OwnedCommonParam) -> Self {
+        VerifierService {
+            lib,
+            common_params: Arc::new(common_params),
+            store: None,
+            challenge_timeout: Duration::from_secs(60),
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Persist every miner to store, and restore the miners already in it
+    pub fn with_store(mut self, store: Arc<dyn VerifierStore>) -> Result<Self, StoreError> {
+        let sessions = rehydrate_sessions(store.as_ref())?;
+        self.sessions = Mutex::new(
+            sessions
+                .into_iter()
+                .map(|(id, session)| (id, Arc::new(Mutex::new(session))))
+                .collect(),
+        );
+        self.store = Some(store);
+        Ok(self)
+    }
+
+    // Time a miner has to answer its challenge
+    pub fn with_challenge_timeout(mut self, timeout: Duration) -> Self {
+        self.challenge_timeout = timeout;
+        self
+    }
+
+    // A logged out miner may register again
+    #[tracing::instrument(level = "debug", skip_all, fields(miner = ?id))]
+    pub async fn register(&self, id: ProverId) -> Result<(), ServiceError> {
+        // The map is only locked to look up or insert, never along with a session
+        let existing = lock(&self.sessions).get(&id).cloned();
+        let record = match existing {
+            Some(existing) => {
+                let mut session = lock(&existing);
+                if session.state() != MinerState::LoggedOut {
+                    return Err(ServiceError::AlreadyRegistered);
+                }
+                *session = MinerSession::register(id);
+                session.to_record()
+            }
+            None => {
+                let session = MinerSession::register(id.clone());
+                let record = session.to_record();
+                match lock(&self.sessions).entry(id) {
+                    // Registered by another call in the meantime
+                    Entry::Occupied(_) => return Err(ServiceError::AlreadyRegistered),
+                    Entry::Vacant(entry) => entry.insert(Arc::new(Mutex::new(session))),
+                };
+                record
+            }
+        };
+        self.persist(record).await
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(miner = ?id))]
+    pub async fn submit_commits(&self, id: &ProverId, commits: Vec<Commit>) -> Result<(), ServiceError> {
+        let session = self.session(id)?;
+        let record = {
+            let mut session = lock(&session);
+            session.receive_commits(commits)?;
+            session.to_record()
+        };
+        self.persist(record).await
+    }
+
+    // The pending challenge, generated for the submitted commits if needed.
+    // The session is not held while Go generates it, so two calls may both
+    // ask Go, the first to come back sets the challenge of the miner.
+    #[tracing::instrument(level = "debug", skip_all, fields(miner = ?id))]
+    pub async fn get_challenge(&self, id: &ProverId) -> Result<Challenge, ServiceError> {
+        let session = self.session(id)?;
+        let (lib, common_params) = (self.lib.clone(), self.common_params.clone());
+        let commits = {
+            let session = lock(&session);
+            if let Some(pending) = session.pending_challenge() {
+                return Ok(pending.challenge.clone());
+            }
+            if session.state() != MinerState::CommitsReceived {
+                return Err(ServiceError::Session(SessionError::InvalidTransition {
+                    from: session.state(),
+                    action: "challenge",
+                }));
+            }
+            session.commits().to_vec()
+        };
+
+        let call_id = id.clone();
+        let challenge = blocking(move || {
+            lib.try_generate_commit_challenge(&commits, &mut common_params.to_c(), &call_id)
+                .map_err(|e| match e {
+                    CallError::Go(GoError { kind: GoErrorKind::InvalidInput, .. }) => ServiceError::CommitsRejected,
+                    e => ServiceError::Internal(e.to_string()),
+                })
+        })
+        .await?;
+        if challenge.0.is_empty() {
+            return Err(ServiceError::CommitsRejected);
+        }
+
+        let mut session = lock(&session);
+        if let Some(pending) = session.pending_challenge() {
+            return Ok(pending.challenge.clone());
+        }
+        // Fails if the miner moved on, e.g. logged out, in the meantime
+        session.challenge(challenge.clone(), Instant::now() + self.challenge_timeout)?;
+        Ok(challenge)
+    }
+
+    // Go verifies the proofs without the session being held, the miner is
+    // Verifying until it is done, see MinerSession::begin_commit_proofs
+    #[tracing::instrument(level = "debug", skip_all, fields(miner = ?id))]
+    pub async fn submit_commit_proofs(
+        &self,
+        id: &ProverId,
+        proofs: Vec<Vec<CommitProof>>,
+    ) -> Result<(), ServiceError> {
+        let session = self.session(id)?;
+        if !self.lib.can_verify() {
+            return Err(ServiceError::Unsupported("commit proof verification"));
+        }
+        let (lib, common_params) = (self.lib.clone(), self.common_params.clone());
+
+        let begun = {
+            let mut session = lock(&session);
+            session.begin_commit_proofs(Instant::now()).map_err(|e| (e, session.to_record()))
+        };
+        let challenge = match begun {
+            Ok(challenge) => challenge,
+            // Late proofs send the miner back to Registered, which is stored as well
+            Err((e, record)) => {
+                self.persist(record).await?;
+                return Err(ServiceError::Session(e));
+            }
+        };
+
+        let call_id = id.clone();
+        let verdict = blocking(move || {
+            Ok(lib.verify_commit_and_acc_proofs(proofs, challenge, &mut common_params.to_c(), &call_id))
+        })
+        .await
+        .unwrap_or_else(|e| Err(VerifyError::Failed(e.to_string())));
+
+        // A rejected miner is back to Registered, which is stored as well
+        let (result, record) = {
+            let mut session = lock(&session);
+            (session.finish_commit_proofs(verdict), session.to_record())
+        };
+        self.persist(record).await?;
+        result.map_err(ServiceError::Session)
+    }
+
+    pub fn verify_space(&self, id: &ProverId, _proof: &SpaceProof) -> Result<(), ServiceError> {
+        self.expect_audited(id, "audit space of")?;
+        Err(ServiceError::Unsupported("space proof verification"))
+    }
+
+    pub fn verify_deletion(&self, id: &ProverId, _proof: &DeletionProof) -> Result<(), ServiceError> {
+        self.expect_audited(id, "verify deletion of")?;
+        Err(ServiceError::Unsupported("deletion proof verification"))
+    }
+
+    fn expect_audited(&self, id: &ProverId, action: &'static str) -> Result<(), ServiceError> {
+        let session = self.session(id)?;
+        let state = lock(&session).state();
+        if !matches!(state, MinerState::Proven | MinerState::SpaceAudited) {
+            return Err(ServiceError::Session(SessionError::InvalidTransition { from: state, action }));
+        }
+        Ok(())
+    }
+
+    fn session(&self, id: &ProverId) -> Result<SharedSession, ServiceError> {
+        lock(&self.sessions).get(id).cloned().ok_or(ServiceError::UnknownMiner)
+    }
+
+    // Store I/O runs on the blocking pool as well
+    async fn persist(&self, record: ProverNodeRecord) -> Result<(), ServiceError> {
+        if let Some(store) = self.store.clone() {
+            blocking(move || store.put(&record).map_err(ServiceError::Store)).await?;
+        }
+        Ok(())
+    }
+}
+
+async fn blocking<T, F>(f: F) -> Result<T, ServiceError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, ServiceError> + Send + 'static,
+{
+    task::spawn_blocking(f)
+        .await
+        .map_err(|e| ServiceError::Internal(e.to_string()))?
+}
@@ -0,0 +1,260 @@
+use std::{fmt, time::Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ffi::VerifyError,
+    store::ProverNodeRecord,
+    types::{Challenge, Commit, CommitProof, ProverId},
+};
+
+// Where a miner is in the proof of idle space protocol.
+//
+//   Registered -> CommitsReceived -> Challenged -> Verifying -> Proven -> SpaceAudited
+//        ^                                |             |           |            |
+//        +------- proofs late ------------+             |           +------------+-> CommitsReceived
+//        +------- proofs rejected ----------------------+
+//
+// A miner is Verifying while Go checks its proofs, which is done without
+// holding the session, see begin_commit_proofs and finish_commit_proofs.
+// Proven and audited miners may commit more files, an audited miner
+// stays SpaceAudited until it does: proving them makes it Proven again,
+// through CommitsReceived. Any state but
+// LoggedOut may log out, as the Go verifier does with IsLogout, and a
+// logged out miner accepts nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum MinerState {
+    Registered,
+    CommitsReceived,
+    Challenged,
+    Verifying,
+    Proven,
+    SpaceAudited,
+    LoggedOut,
+}
+
+// The commit challenge a miner has to answer before the deadline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingChallenge {
+    pub challenge: Challenge,
+    pub deadline: Instant,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionError {
+    // The action is not allowed in the current state
+    InvalidTransition { from: MinerState, action: &'static str },
+    // A commit for a file that was already proven
+    StaleCommit { file_index: i64, count: i64 },
+    // The challenge does not cover exactly the received commits
+    ChallengeMismatch,
+    DeadlineExpired { deadline: Instant },
+    Rejected(VerifyError),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::InvalidTransition { from, action } => {
+                write!(f, "can not {} a miner in state {:?}", action, from)
+            }
+            SessionError::StaleCommit { file_index, count } => write!(
+                f,
+                "commit for file {} but {} files are already proven",
+                file_index, count
+            ),
+            SessionError::ChallengeMismatch => {
+                write!(f, "challenge does not match the received commits")
+            }
+            SessionError::DeadlineExpired { deadline } => {
+                write!(f, "challenge deadline {:?} expired", deadline)
+            }
+            SessionError::Rejected(e) => write!(f, "proofs rejected: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+pub struct MinerSession {
+    id: ProverId,
+    state: MinerState,
+    commits: Vec<Commit>,
+    pending: Option<PendingChallenge>,
+    // Number of files proven so far
+    count: i64,
+}
+
+impl MinerSession {
+    pub fn register(id: ProverId) -> Self {
+        MinerSession {
+            id,
+            state: MinerState::Registered,
+            commits: Vec::new(),
+            pending: None,
+            count: 0,
+        }
+    }
+
+    // Restore a session from a stored record, in the state it was stored
+    // in. A pending challenge is not stored, so a miner that was challenged
+    // or being verified has to be challenged again. Records without a state
+    // get the one their commits and count suggest.
+    pub fn restore(record: ProverNodeRecord) -> Self {
+        let state = match record.state {
+            Some(MinerState::Challenged | MinerState::Verifying) => MinerState::CommitsReceived,
+            Some(state) => state,
+            None if !record.commits.is_empty() => MinerState::CommitsReceived,
+            None if record.count > 0 => MinerState::Proven,
+            None => MinerState::Registered,
+        };
+        MinerSession {
+            id: record.id,
+            state,
+            commits: record.commits,
+            pending: None,
+            count: record.count,
+        }
+    }
+
+    pub fn to_record(&self) -> ProverNodeRecord {
+        ProverNodeRecord {
+            id: self.id.clone(),
+            count: self.count,
+            commits: self.commits.clone(),
+            state: Some(self.state),
+        }
+    }
+
+    pub fn id(&self) -> &ProverId {
+        &self.id
+    }
+
+    pub fn state(&self) -> MinerState {
+        self.state
+    }
+
+    pub fn commits(&self) -> &[Commit] {
+        &self.commits
+    }
+
+    pub fn pending_challenge(&self) -> Option<&PendingChallenge> {
+        self.pending.as_ref()
+    }
+
+    pub fn count(&self) -> i64 {
+        self.count
+    }
+
+    pub fn receive_commits(&mut self, commits: Vec<Commit>) -> Result<(), SessionError> {
+        self.expect_state(
+            &[MinerState::Registered, MinerState::Proven, MinerState::SpaceAudited],
+            "receive commits from",
+        )?;
+        if let Some(stale) = commits.iter().find(|c| c.file_index <= self.count) {
+            return Err(SessionError::StaleCommit {
+                file_index: stale.file_index,
+                count: self.count,
+            });
+        }
+
+        self.commits = commits;
+        self.state = MinerState::CommitsReceived;
+        Ok(())
+    }
+
+    pub fn challenge(&mut self, challenge: Challenge, deadline: Instant) -> Result<(), SessionError> {
+        self.expect_state(&[MinerState::CommitsReceived], "challenge")?;
+        let challenged = challenge.0.iter().map(|c| c.file_index);
+        if !challenged.eq(self.commits.iter().map(|c| c.file_index)) {
+            return Err(SessionError::ChallengeMismatch);
+        }
+
+        self.pending = Some(PendingChallenge { challenge, deadline });
+        self.state = MinerState::Challenged;
+        Ok(())
+    }
+
+    // Check the proofs for the pending challenge with verify, usually
+    // PoisLibrary::verify_commit_and_acc_proofs. Late or rejected proofs send
+    // the miner back to Registered, its commits have to be submitted again.
+    pub fn submit_commit_proofs<F>(
+        &mut self,
+        proofs: Vec<Vec<CommitProof>>,
+        now: Instant,
+        verify: F,
+    ) -> Result<(), SessionError>
+    where
+        F: FnOnce(&ProverId, Vec<Vec<CommitProof>>, Challenge) -> Result<(), VerifyError>,
+    {
+        let challenge = self.begin_commit_proofs(now)?;
+        let verdict = verify(&self.id, proofs, challenge);
+        self.finish_commit_proofs(verdict)
+    }
+
+    // submit_commit_proofs in two steps, for verifying without holding the
+    // session: the challenge the proofs answer, the miner being Verifying
+    // until finish_commit_proofs is given the verdict.
+    pub fn begin_commit_proofs(&mut self, now: Instant) -> Result<Challenge, SessionError> {
+        self.expect_state(&[MinerState::Challenged], "submit commit proofs for")?;
+        let pending = self.pending.take().expect("a challenged miner has a pending challenge");
+
+        if now > pending.deadline {
+            self.commits.clear();
+            self.state = MinerState::Registered;
+            return Err(SessionError::DeadlineExpired {
+                deadline: pending.deadline,
+            });
+        }
+        self.state = MinerState::Verifying;
+        Ok(pending.challenge)
+    }
+
+    pub fn finish_commit_proofs(&mut self, verdict: Result<(), VerifyError>) -> Result<(), SessionError> {
+        self.expect_state(&[MinerState::Verifying], "finish verifying")?;
+
+        let commits = std::mem::take(&mut self.commits);
+        match verdict {
+            Ok(()) => {
+                self.count = commits.iter().map(|c| c.file_index).max().unwrap_or(self.count);
+                self.state = MinerState::Proven;
+                Ok(())
+            }
+            Err(e) => {
+                self.state = MinerState::Registered;
+                Err(SessionError::Rejected(e))
+            }
+        }
+    }
+
+    pub fn space_audited(&mut self) -> Result<(), SessionError> {
+        self.expect_state(&[MinerState::Proven, MinerState::SpaceAudited], "audit space of")?;
+        self.state = MinerState::SpaceAudited;
+        Ok(())
+    }
+
+    pub fn logout(&mut self) -> Result<(), SessionError> {
+        if self.state == MinerState::LoggedOut {
+            return Err(SessionError::InvalidTransition {
+                from: self.state,
+                action: "log out",
+            });
+        }
+
+        self.commits.clear();
+        self.pending = None;
+        self.state = MinerState::LoggedOut;
+        Ok(())
+    }
+
+    fn expect_state(&self, allowed: &[MinerState], action: &'static str) -> Result<(), SessionError> {
+        if allowed.contains(&self.state) {
+            Ok(())
+        } else {
+            Err(SessionError::InvalidTransition {
+                from: self.state,
+                action,
+            })
+        }
+    }
+}
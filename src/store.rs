@@ -0,0 +1,220 @@
+use std::{
+    collections::HashMap,
+    fmt, io,
+    sync::{Mutex, MutexGuard},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    session::{MinerSession, MinerState},
+    types::{Commit, ProverId},
+    utils::lock,
+};
+
+// Persistent verifier state.
+//
+// The Go verifier keeps a ProverNode per miner (id, commits buffer,
+// accumulator and count) in memory only. A VerifierStore keeps the same
+// record, but for the accumulator, which only Go computes and no export
+// hands back, so a restarted verifier can rehydrate its MinerSessions.
+// Records and snapshots written with an acc field still load, it is
+// ignored.
+// Along with the state of the miner, as the service sees it. Pending
+// challenges are not stored, a restored miner with commits is challenged
+// again.
+//
+// The Go library has no export to load prover nodes back, so only the
+// sessions on this side can be rehydrated for now.
+
+// Mirrors ProverNode of the Go verifier
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ProverNodeRecord {
+    pub id: ProverId,
+    pub count: i64,
+    // Commits received and not proven yet
+    pub commits: Vec<Commit>,
+    // Where the miner was in the protocol, None in records written before
+    // it was stored, see MinerSession::restore
+    #[serde(default)]
+    pub state: Option<MinerState>,
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    Io(io::Error),
+    Encoding(serde_json::Error),
+    Backend(String),
+    NotFound(ProverId),
+    // A snapshot written by a newer version of this crate
+    UnsupportedSnapshot { version: u32 },
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Io(e) => write!(f, "store io error: {}", e),
+            StoreError::Encoding(e) => write!(f, "store encoding error: {}", e),
+            StoreError::Backend(e) => write!(f, "store backend error: {}", e),
+            StoreError::NotFound(id) => write!(f, "no prover node for id {:?}", id),
+            StoreError::UnsupportedSnapshot { version } => {
+                write!(f, "unsupported prover node snapshot version {}", version)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<serde_json::Error> for StoreError {
+    fn from(e: serde_json::Error) -> Self {
+        StoreError::Encoding(e)
+    }
+}
+
+pub trait VerifierStore: Send + Sync {
+    // Insert or replace the record of node.id
+    fn put(&self, node: &ProverNodeRecord) -> Result<(), StoreError>;
+
+    fn get(&self, id: &ProverId) -> Result<Option<ProverNodeRecord>, StoreError>;
+
+    fn remove(&self, id: &ProverId) -> Result<(), StoreError>;
+
+    fn load_all(&self) -> Result<Vec<ProverNodeRecord>, StoreError>;
+}
+
+// Restore a session for every stored miner
+pub fn rehydrate_sessions(
+    store: &dyn VerifierStore,
+) -> Result<HashMap<ProverId, MinerSession>, StoreError> {
+    Ok(store
+        .load_all()?
+        .into_iter()
+        .map(|node| (node.id.clone(), MinerSession::restore(node)))
+        .collect())
+}
+
+// Prover node snapshots, to migrate a miner between verifiers or back it up.
+//
+// A snapshot is the JSON of the node record tagged with a version. These
+// are Rust side only: ProverNodeC in c_types was never exported by the Go
+// library, so a node imported here is not known to the Go verifier.
+// Version 2 dropped acc from the node.
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+#[derive(Deserialize, Serialize)]
+struct ProverNodeSnapshot {
+    version: u32,
+    node: ProverNodeRecord,
+}
+
+pub fn export_prover_node(store: &dyn VerifierStore, id: &ProverId) -> Result<Vec<u8>, StoreError> {
+    let node = store.get(id)?.ok_or_else(|| StoreError::NotFound(id.clone()))?;
+    Ok(serde_json::to_vec(&ProverNodeSnapshot {
+        version: SNAPSHOT_VERSION,
+        node,
+    })?)
+}
+
+// Replaces any node stored with the same id
+pub fn import_prover_node(store: &dyn VerifierStore, snapshot: &[u8]) -> Result<ProverId, StoreError> {
+    let snapshot: ProverNodeSnapshot = serde_json::from_slice(snapshot)?;
+    if snapshot.version > SNAPSHOT_VERSION {
+        return Err(StoreError::UnsupportedSnapshot {
+            version: snapshot.version,
+        });
+    }
+    store.put(&snapshot.node)?;
+    Ok(snapshot.node.id)
+}
+
+// For tests and verifiers that do not need to survive a restart
+#[derive(Default)]
+pub struct MemoryStore {
+    nodes: Mutex<HashMap<ProverId, ProverNodeRecord>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn nodes(&self) -> MutexGuard<'_, HashMap<ProverId, ProverNodeRecord>> {
+        lock(&self.nodes)
+    }
+}
+
+impl VerifierStore for MemoryStore {
+    fn put(&self, node: &ProverNodeRecord) -> Result<(), StoreError> {
+        self.nodes().insert(node.id.clone(), node.clone());
+        Ok(())
+    }
+
+    fn get(&self, id: &ProverId) -> Result<Option<ProverNodeRecord>, StoreError> {
+        Ok(self.nodes().get(id).cloned())
+    }
+
+    fn remove(&self, id: &ProverId) -> Result<(), StoreError> {
+        self.nodes().remove(id);
+        Ok(())
+    }
+
+    fn load_all(&self) -> Result<Vec<ProverNodeRecord>, StoreError> {
+        Ok(self.nodes().values().cloned().collect())
+    }
+}
+
+// Embedded on-disk store, one JSON record per miner keyed by its id
+#[cfg(feature = "sled")]
+pub struct SledStore {
+    db: sled::Db,
+}
+
+#[cfg(feature = "sled")]
+impl SledStore {
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self, StoreError> {
+        Ok(SledStore {
+            db: sled::open(path).map_err(sled_error)?,
+        })
+    }
+}
+
+#[cfg(feature = "sled")]
+fn sled_error(e: sled::Error) -> StoreError {
+    match e {
+        sled::Error::Io(e) => StoreError::Io(e),
+        e => StoreError::Backend(e.to_string()),
+    }
+}
+
+#[cfg(feature = "sled")]
+impl VerifierStore for SledStore {
+    fn put(&self, node: &ProverNodeRecord) -> Result<(), StoreError> {
+        self.db
+            .insert(node.id.as_bytes(), serde_json::to_vec(node)?)
+            .map_err(sled_error)?;
+        self.db.flush().map_err(sled_error)?;
+        Ok(())
+    }
+
+    fn get(&self, id: &ProverId) -> Result<Option<ProverNodeRecord>, StoreError> {
+        match self.db.get(id.as_bytes()).map_err(sled_error)? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn remove(&self, id: &ProverId) -> Result<(), StoreError> {
+        self.db.remove(id.as_bytes()).map_err(sled_error)?;
+        self.db.flush().map_err(sled_error)?;
+        Ok(())
+    }
+
+    fn load_all(&self) -> Result<Vec<ProverNodeRecord>, StoreError> {
+        self.db
+            .iter()
+            .values()
+            .map(|bytes| Ok(serde_json::from_slice(&bytes.map_err(sled_error)?)?))
+            .collect()
+    }
+}
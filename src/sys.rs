@@ -0,0 +1,116 @@
+#![allow(non_camel_case_types, non_snake_case)]
+#![deny(clashing_extern_declarations)]
+
+// Raw declarations of the Go library, as cgo exports them in cgo/main.h.
+//
+// Nothing calls these directly, they are the reference the hand written
+// c_types and the function types of ffi are checked against at compile
+// time: the layout assertions below and, for the function signatures,
+// the clashing_extern_declarations lint on ffi's own declarations.
+
+#[cfg(feature = "bindgen")]
+include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+
+#[cfg(not(feature = "bindgen"))]
+include!("sys/bindings.rs");
+
+// Our struct must have the size, alignment and field offsets of the
+// header's, and each field the header's type, our structs standing for
+// theirs behind pointers. Both are destructured without `..`, so a field
+// missing on either side fails the build too.
+macro_rules! assert_layout {
+    ($ours:path => $theirs:ident { $($field:ident: $their_field:ident),+ $(,)? }) => {
+        impl Raw for $ours {
+            type Raw = $theirs;
+        }
+
+        const _: () = {
+            use std::mem::{align_of, offset_of, size_of};
+
+            assert!(size_of::<$ours>() == size_of::<$theirs>());
+            assert!(align_of::<$ours>() == align_of::<$theirs>());
+            $(assert!(offset_of!($ours, $field) == offset_of!($theirs, $their_field));)+
+        };
+
+        const _: fn($ours, $theirs) = |ours, theirs| {
+            $(same_type(ours.$field, theirs.$their_field);)+
+            let $ours { $($field: _),+ } = ours;
+            let $theirs { $($their_field: _),+ } = theirs;
+        };
+    };
+}
+
+// The header's type for each of ours
+trait Raw {
+    type Raw;
+}
+
+macro_rules! raw_as_is {
+    ($($t:ty),+) => {
+        $(impl Raw for $t {
+            type Raw = $t;
+        })+
+    };
+}
+
+raw_as_is!(i8, u8, i32, i64);
+
+impl<T: Raw> Raw for *mut T {
+    type Raw = *mut T::Raw;
+}
+
+fn same_type<T: Raw>(_: T, _: T::Raw) {}
+
+mod layout {
+    use super::*;
+    use crate::c_types;
+
+    assert_layout!(c_types::CommitC => CommitC {
+        file_index: fileIndex,
+        roots: roots,
+        roots_length: rootsLength,
+        sub_roots_lengths: subRootsLengths,
+    });
+    assert_layout!(c_types::MhtProofC => MhtProofC {
+        index: index,
+        label: label,
+        label_length: labelLength,
+        paths: paths,
+        sub_paths_lengths: subPathsLengths,
+        path_length: pathLength,
+        locs: locs,
+        locs_length: locsLength,
+    });
+    assert_layout!(c_types::CommitProofC => CommitProofC {
+        node: node,
+        parents: parents,
+        parents_length: parentsLength,
+    });
+    assert_layout!(c_types::MyByte => MyByte { b: b, length: length });
+    assert_layout!(c_types::CommonParam => CommonParam {
+        key_n: keyN,
+        key_g: keyG,
+        k: kC,
+        n: nC,
+        d: dC,
+    });
+    assert_layout!(c_types::ProverID => ProverID { id: id, length: length });
+    assert_layout!(c_types::I64ArrOfArr => I64ArrOfArr {
+        main_array: mainArray,
+        sub_array_lengths: sub_array_lengths,
+        length: length,
+    });
+    assert_layout!(c_types::Inner => Inner { num: num });
+    assert_layout!(c_types::Outer => Outer { i: i });
+    assert_layout!(c_types::GenerateCommitChallengeReturn => GenerateCommitChallenge_return {
+        r0: r0,
+        r1: r1,
+        r2: r2,
+    });
+    assert_layout!(c_types::ReturnAnArrayReturn => ReturnAnArray_return { r0: r0, r1: r1 });
+    assert_layout!(c_types::ReturnArrayofArraysReturn => ReturnArrayofArrays_return {
+        r0: r0,
+        r1: r1,
+        r2: r2,
+    });
+}
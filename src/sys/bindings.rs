@@ -0,0 +1,136 @@
+// Raw bindings of cgo/main.h in the format bindgen writes them, with
+// .layout_tests(false), from the header and the vendored cgo/verifier.h.
+// The bindgen feature regenerates them into OUT_DIR, see build.rs, and
+// warns when they differ from this file, which is used otherwise.
+
+pub type NodeType = i32;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct CommitC {
+    pub fileIndex: i64,
+    pub roots: *mut *mut ::std::os::raw::c_uchar,
+    pub rootsLength: ::std::os::raw::c_int,
+    pub subRootsLengths: *mut ::std::os::raw::c_int,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct MhtProofC {
+    pub index: NodeType,
+    pub label: *mut ::std::os::raw::c_uchar,
+    pub labelLength: ::std::os::raw::c_int,
+    pub paths: *mut *mut ::std::os::raw::c_uchar,
+    pub subPathsLengths: *mut ::std::os::raw::c_int,
+    pub pathLength: ::std::os::raw::c_int,
+    pub locs: *mut ::std::os::raw::c_uchar,
+    pub locsLength: ::std::os::raw::c_int,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct CommitProofC {
+    pub node: *mut MhtProofC,
+    pub parents: *mut *mut MhtProofC,
+    pub parentsLength: ::std::os::raw::c_int,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct MyByte {
+    pub b: *mut ::std::os::raw::c_uchar,
+    pub length: ::std::os::raw::c_int,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct CommonParam {
+    pub keyN: *mut ::std::os::raw::c_char,
+    pub keyG: *mut ::std::os::raw::c_char,
+    pub kC: i64,
+    pub nC: i64,
+    pub dC: i64,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct ProverID {
+    pub id: *mut ::std::os::raw::c_char,
+    pub length: ::std::os::raw::c_int,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct I64ArrOfArr {
+    pub mainArray: *mut *mut i64,
+    pub sub_array_lengths: *mut ::std::os::raw::c_int,
+    pub length: ::std::os::raw::c_int,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct Inner {
+    pub num: ::std::os::raw::c_int,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct Outer {
+    pub i: *mut Inner,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct GenerateCommitChallenge_return {
+    pub r0: *mut *mut i64,
+    pub r1: *mut ::std::os::raw::c_int,
+    pub r2: ::std::os::raw::c_int,
+}
+extern "C" {
+    pub fn GenerateCommitChallenge(
+        commitsC: *mut CommitC,
+        length: ::std::os::raw::c_int,
+        commonParamsC: *mut CommonParam,
+        proverID: *mut ProverID,
+    ) -> GenerateCommitChallenge_return;
+}
+extern "C" {
+    pub fn VerifyCommitAndAccProofs(
+        commitProofC: *mut *mut CommitProofC,
+        commitProof_length: ::std::os::raw::c_int,
+        challengeC: *mut I64ArrOfArr,
+        commonParamsC: *mut CommonParam,
+        proverIDC: *mut ProverID,
+    );
+}
+extern "C" {
+    pub fn GetByteArray(b: *mut ::std::os::raw::c_uchar, length: ::std::os::raw::c_int);
+}
+extern "C" {
+    pub fn GetByteArrayAsStruct(b: *mut MyByte);
+}
+extern "C" {
+    pub fn GetByteArrayAsStructArray(myByte: *mut MyByte, length: ::std::os::raw::c_long);
+}
+extern "C" {
+    pub fn GetByteArrayOfArray(
+        array: *mut *mut ::std::os::raw::c_uchar,
+        length: ::std::os::raw::c_int,
+        lengths: *mut ::std::os::raw::c_int,
+    );
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct ReturnAnArray_return {
+    pub r0: *mut ::std::os::raw::c_int,
+    pub r1: *mut ::std::os::raw::c_int,
+}
+extern "C" {
+    pub fn ReturnAnArray() -> ReturnAnArray_return;
+}
+extern "C" {
+    pub fn FreeArray(arr: *mut ::std::os::raw::c_int);
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct ReturnArrayofArrays_return {
+    pub r0: *mut *mut ::std::os::raw::c_int,
+    pub r1: *mut ::std::os::raw::c_int,
+    pub r2: ::std::os::raw::c_int,
+}
+extern "C" {
+    pub fn ReturnArrayofArrays() -> ReturnArrayofArrays_return;
+}
+extern "C" {
+    pub fn GetOuterArrOfArrWithInner(outer: *mut *mut Outer);
+}
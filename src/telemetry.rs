@@ -0,0 +1,127 @@
+#[cfg(feature = "metrics")]
+use std::time::Instant;
+use std::time::Duration;
+
+use crate::ffi::VerifyError;
+
+// Metrics of the protocol steps, recorded with the metrics crate when the
+// metrics feature is on and compiled out otherwise. Install any recorder,
+// e.g. a Prometheus exporter, to export them, and describe() to give them
+// their help text.
+//
+// Spans of the same steps are always emitted with tracing.
+
+pub const CHALLENGES_GENERATED: &str = "pois_challenges_generated_total";
+// Only proofs Go verified, libraries without a verdict never count any,
+// see PoisLibrary::can_verify
+pub const PROOFS_VERIFIED: &str = "pois_proofs_verified_total";
+// Labelled with reason, see rejection_reason
+pub const PROOFS_REJECTED: &str = "pois_proofs_rejected_total";
+
+// Latencies in seconds. MARSHAL_SECONDS is labelled with what was marshalled.
+pub const CHALLENGE_SECONDS: &str = "pois_challenge_generation_seconds";
+// VERIFY_SECONDS times the Go call, PRECHECK_SECONDS the checks of validate
+// run before it.
+pub const VERIFY_SECONDS: &str = "pois_verification_seconds";
+pub const PRECHECK_SECONDS: &str = "pois_precheck_seconds";
+pub const MARSHAL_SECONDS: &str = "pois_marshal_seconds";
+
+#[cfg(feature = "metrics")]
+pub fn describe() {
+    use metrics::{describe_counter, describe_histogram, Unit};
+
+    describe_counter!(CHALLENGES_GENERATED, "Commit challenges generated by Go");
+    describe_counter!(PROOFS_VERIFIED, "Commit proofs of a miner accepted by Go");
+    describe_counter!(PROOFS_REJECTED, "Commit proofs of a miner rejected, by reason");
+    describe_histogram!(CHALLENGE_SECONDS, Unit::Seconds, "Time to generate a commit challenge");
+    describe_histogram!(VERIFY_SECONDS, Unit::Seconds, "Time Go takes to verify the commit proofs of a miner");
+    describe_histogram!(PRECHECK_SECONDS, Unit::Seconds, "Time to check the shape of the commit proofs of a miner");
+    describe_histogram!(MARSHAL_SECONDS, Unit::Seconds, "Time to marshal arguments to or from C");
+}
+
+pub fn rejection_reason(e: &VerifyError) -> &'static str {
+    use crate::{diagnostics::GoErrorKind, validate::ProofShapeError};
+
+    match e {
+        VerifyError::Malformed(e) => match e {
+            ProofShapeError::MissingFile { .. } => "missing_file",
+            ProofShapeError::ExtraFiles { .. } => "extra_files",
+            ProofShapeError::NodeCount { .. } => "node_count",
+            ProofShapeError::MissingNode { .. } => "missing_node",
+            ProofShapeError::IndexMismatch { .. } => "index_mismatch",
+            ProofShapeError::ParentCount { .. } => "parent_count",
+            ProofShapeError::InvalidSelector { .. } => "invalid_selector",
+            ProofShapeError::BadPath { .. } => "bad_path",
+        },
+        VerifyError::Challenge(_) => "malformed_challenge",
+        VerifyError::Failed(_) => "panic",
+        VerifyError::Go(e) => match e.kind {
+            GoErrorKind::InvalidInput => "go_invalid_input",
+            GoErrorKind::Panic => "go_panic",
+            GoErrorKind::Other(_) => "go_error",
+        },
+        VerifyError::NoVerdict => "no_verdict",
+    }
+}
+
+pub(crate) fn record_verdict(result: &Result<(), VerifyError>) {
+    #[cfg(feature = "metrics")]
+    match result {
+        Ok(()) => metrics::counter!(PROOFS_VERIFIED).increment(1),
+        Err(e) => metrics::counter!(PROOFS_REJECTED, "reason" => rejection_reason(e)).increment(1),
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = result;
+}
+
+pub(crate) fn record_challenge() {
+    #[cfg(feature = "metrics")]
+    metrics::counter!(CHALLENGES_GENERATED).increment(1);
+}
+
+// Records a time measured elsewhere, e.g. on another thread
+pub(crate) fn record_seconds(name: &'static str, elapsed: Duration) {
+    #[cfg(feature = "metrics")]
+    metrics::histogram!(name).record(elapsed);
+    #[cfg(not(feature = "metrics"))]
+    let _ = (name, elapsed);
+}
+
+// Records the time until it is dropped into a histogram
+pub(crate) struct Timer {
+    #[cfg(feature = "metrics")]
+    histogram: metrics::Histogram,
+    #[cfg(feature = "metrics")]
+    start: Instant,
+}
+
+impl Timer {
+    pub(crate) fn start(name: &'static str) -> Self {
+        #[cfg(not(feature = "metrics"))]
+        let _ = name;
+        Timer {
+            #[cfg(feature = "metrics")]
+            histogram: metrics::histogram!(name),
+            #[cfg(feature = "metrics")]
+            start: Instant::now(),
+        }
+    }
+
+    pub(crate) fn marshal(what: &'static str) -> Self {
+        #[cfg(not(feature = "metrics"))]
+        let _ = what;
+        Timer {
+            #[cfg(feature = "metrics")]
+            histogram: metrics::histogram!(MARSHAL_SECONDS, "what" => what),
+            #[cfg(feature = "metrics")]
+            start: Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl Drop for Timer {
+    fn drop(&mut self) {
+        self.histogram.record(self.start.elapsed());
+    }
+}
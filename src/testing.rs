@@ -0,0 +1,58 @@
+use sha2::{Digest, Sha512};
+
+use crate::types::{Challenge, Commit, CommitProof, MhtProof};
+
+// Inputs for the tests and the benches, also handy to exercise code built
+// on the verifier.
+
+// Commits Go accepts: k + 2 roots per file, the last one being the
+// SHA-512 of the others.
+pub fn make_commits(k: i64, files: i64, seed: u8) -> Vec<Commit> {
+    (1..=files)
+        .map(|file_index| {
+            let mut roots: Vec<Vec<u8>> = (0..=k)
+                .map(|r| vec![seed.wrapping_add(r as u8).wrapping_mul(file_index as u8); 64])
+                .collect();
+            let mut hash = Sha512::new();
+            for root in &roots {
+                hash.update(root);
+            }
+            roots.push(hash.finalize().to_vec());
+            Commit { file_index, roots }
+        })
+        .collect()
+}
+
+// Commit proofs matching the shape of the challenge, as validate checks
+// it, with made up labels and paths. Go would not accept them.
+pub fn shaped_proofs(challenge: &Challenge, n: i64, d: i64) -> Vec<Vec<CommitProof>> {
+    let mht = |index: i64| MhtProof { index: index as i32, label: vec![0; 64], paths: vec![vec![0; 64]], locs: vec![0] };
+
+    challenge
+        .0
+        .iter()
+        .map(|chal| {
+            let mut index = chal.layer_selector;
+            let mut proofs = Vec::new();
+            for selector in chal.node_indices.iter().map(Some).chain([None]) {
+                let layer = index / n;
+                let parents: Vec<MhtProof> = if layer > 0 {
+                    (0..=d).map(|p| mht((layer - 1) * n + (index + p) % n)).collect()
+                } else {
+                    vec![]
+                };
+                // A selector out of range has no parent to walk down to,
+                // the proofs stay on the node and validate rejects them
+                let next = selector
+                    .and_then(|&s| usize::try_from(s).ok())
+                    .and_then(|s| parents.get(s))
+                    .map(|parent| parent.index as i64);
+                proofs.push(CommitProof { node: Some(mht(index)), parents });
+                if let Some(next) = next {
+                    index = next;
+                }
+            }
+            proofs
+        })
+        .collect()
+}
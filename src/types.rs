@@ -1,12 +1,15 @@
+use std::fmt;
+
 use num_bigint_dig::BigUint;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Commit {
     pub file_index: i64,
     pub roots: Vec<Vec<u8>>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct MhtProof {
     pub index: i32,
     pub label: Vec<u8>,
@@ -14,12 +17,206 @@ pub struct MhtProof {
     pub locs: Vec<u8>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct CommitProof {
     pub node: Option<MhtProof>,
     pub parents: Vec<MhtProof>,
 }
 
+// Space and deletion proofs as in cess_pois. The Go library does not
+// export their verification yet, they are only carried by the services.
+
+// A link of an accumulator witness chain, acc is the next level up
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AccWitnessNode {
+    pub elem: Vec<u8>,
+    pub wit: Vec<u8>,
+    pub acc: Option<Box<AccWitnessNode>>,
+}
+
+// Proves the files left..right are still held
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SpaceProof {
+    pub left: i64,
+    pub right: i64,
+    pub proofs: Vec<Vec<MhtProof>>,
+    pub roots: Vec<Vec<u8>>,
+    pub wit_chains: Vec<AccWitnessNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DeletionProof {
+    pub roots: Vec<Vec<u8>>,
+    pub wit_chain: Option<AccWitnessNode>,
+    pub acc_path: Vec<Vec<u8>>,
+}
+
+// Serialized with n and g as decimal strings
+#[derive(Clone, Deserialize, Serialize)]
 pub struct RsaKey {
+    #[serde(with = "decimal")]
     pub n: BigUint,
+    #[serde(with = "decimal")]
     pub g: BigUint,
 }
+
+mod decimal {
+    use num_bigint_dig::BigUint;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &BigUint, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_str_radix(10))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigUint, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        BigUint::parse_bytes(s.as_bytes(), 10).ok_or_else(|| D::Error::custom("invalid decimal integer"))
+    }
+}
+
+// ID of a prover/miner, any bytes such as an account id.
+// Go receives it as a pointer and a length, it is not NUL terminated.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct ProverId(Vec<u8>);
+
+impl ProverId {
+    pub fn new(id: impl Into<Vec<u8>>) -> Self {
+        ProverId(id.into())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl From<&str> for ProverId {
+    fn from(id: &str) -> Self {
+        ProverId(id.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for ProverId {
+    fn from(id: String) -> Self {
+        ProverId(id.into_bytes())
+    }
+}
+
+impl From<&[u8]> for ProverId {
+    fn from(id: &[u8]) -> Self {
+        ProverId(id.to_vec())
+    }
+}
+
+impl From<Vec<u8>> for ProverId {
+    fn from(id: Vec<u8>) -> Self {
+        ProverId(id)
+    }
+}
+
+// Substrate style 32 byte account id
+impl From<[u8; 32]> for ProverId {
+    fn from(id: [u8; 32]) -> Self {
+        ProverId(id.to_vec())
+    }
+}
+
+impl AsRef<[u8]> for ProverId {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+// Challenge for one committed file.
+// Its flat form, as passed to and from Go, is
+// [file_index, layer_selector, node_indices...].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CommitChallenge {
+    pub file_index: i64,
+    // Challenged node of the last expander layer, in n * k .. n * (k + 1)
+    pub layer_selector: i64,
+    // For each layer going down, which parent (0..=d) of the previously
+    // picked node is challenged next
+    pub node_indices: Vec<i64>,
+}
+
+impl CommitChallenge {
+    pub fn from_flat(flat: &[i64]) -> Result<Self, ChallengeError> {
+        match flat {
+            [file_index, layer_selector, node_indices @ ..] => Ok(CommitChallenge {
+                file_index: *file_index,
+                layer_selector: *layer_selector,
+                node_indices: node_indices.to_vec(),
+            }),
+            _ => Err(ChallengeError {
+                position: 0,
+                length: flat.len(),
+            }),
+        }
+    }
+
+    pub fn to_flat(&self) -> Vec<i64> {
+        let mut flat = Vec::with_capacity(self.node_indices.len() + 2);
+        flat.push(self.file_index);
+        flat.push(self.layer_selector);
+        flat.extend_from_slice(&self.node_indices);
+        flat
+    }
+}
+
+// Challenge for a batch of committed files, one entry per file
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub struct Challenge(pub Vec<CommitChallenge>);
+
+impl Challenge {
+    pub fn from_flat(flat: &[Vec<i64>]) -> Result<Self, ChallengeError> {
+        flat.iter()
+            .enumerate()
+            .map(|(i, row)| {
+                CommitChallenge::from_flat(row).map_err(|e| ChallengeError { position: i, ..e })
+            })
+            .collect::<Result<_, _>>()
+            .map(Challenge)
+    }
+
+    pub fn to_flat(&self) -> Vec<Vec<i64>> {
+        self.0.iter().map(CommitChallenge::to_flat).collect()
+    }
+}
+
+impl TryFrom<Vec<Vec<i64>>> for Challenge {
+    type Error = ChallengeError;
+
+    fn try_from(flat: Vec<Vec<i64>>) -> Result<Self, Self::Error> {
+        Challenge::from_flat(&flat)
+    }
+}
+
+impl From<&Challenge> for Vec<Vec<i64>> {
+    fn from(challenge: &Challenge) -> Self {
+        challenge.to_flat()
+    }
+}
+
+// A flat commit challenge without file_index and layer_selector
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChallengeError {
+    // Position of the commit challenge in the batch
+    pub position: usize,
+    pub length: usize,
+}
+
+impl fmt::Display for ChallengeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "commit challenge {} has {} elements, needs at least 2",
+            self.position, self.length
+        )
+    }
+}
+
+impl std::error::Error for ChallengeError {}
@@ -0,0 +1,156 @@
+use std::fmt;
+
+use crate::{
+    merkle::{check_path_shape, PathError},
+    types::{Challenge, CommitProof},
+};
+
+// Structural checks on commit proofs, run before handing them to Go.
+//
+// For a file challenged with [file_index, layer_selector, s_1 .. s_k] the
+// prover answers with k + 1 proofs walking down the expander layers:
+// the first is for node layer_selector, the j-th for the parent s_j of the
+// node proven before it. A node above layer 0 has d + 1 parents, its d
+// parents from the layer below and the node at the same position there,
+// hence a parent selector in 0..=d. Layer 0 nodes have none.
+//
+// The Merkle paths of the node and its parents must have the shape
+// checked by merkle::check_path_shape, which bounds what a hostile proof
+// costs to copy and marshal.
+//
+// These checks are cheap and catch malformed submissions,
+// the labels and Merkle paths are still verified by Go.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProofShapeError {
+    // No proofs for a challenged file
+    MissingFile { file_index: i64 },
+    // Proofs for more files than were challenged
+    ExtraFiles { expected: usize, found: usize },
+    NodeCount { file_index: i64, expected: usize, found: usize },
+    MissingNode { file_index: i64, position: usize },
+    IndexMismatch { file_index: i64, position: usize, expected: i64, found: i64 },
+    ParentCount { file_index: i64, position: usize, expected: usize, found: usize },
+    // The challenge picks a parent the node does not have
+    InvalidSelector { file_index: i64, position: usize, selector: i64 },
+    // The path of the node, or of a parent, is malformed
+    BadPath { file_index: i64, position: usize, error: PathError },
+}
+
+impl fmt::Display for ProofShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofShapeError::MissingFile { file_index } => {
+                write!(f, "no proofs for challenged file {}", file_index)
+            }
+            ProofShapeError::ExtraFiles { expected, found } => {
+                write!(f, "proofs for {} files, {} were challenged", found, expected)
+            }
+            ProofShapeError::NodeCount { file_index, expected, found } => write!(
+                f,
+                "file {}: {} node proofs, expected {}",
+                file_index, found, expected
+            ),
+            ProofShapeError::MissingNode { file_index, position } => {
+                write!(f, "file {}: proof {} has no node", file_index, position)
+            }
+            ProofShapeError::IndexMismatch { file_index, position, expected, found } => write!(
+                f,
+                "file {}: proof {} is for node {}, challenged node {}",
+                file_index, position, found, expected
+            ),
+            ProofShapeError::ParentCount { file_index, position, expected, found } => write!(
+                f,
+                "file {}: proof {} has {} parents, expected {}",
+                file_index, position, found, expected
+            ),
+            ProofShapeError::InvalidSelector { file_index, position, selector } => write!(
+                f,
+                "file {}: proof {} has no parent {}",
+                file_index, position, selector
+            ),
+            ProofShapeError::BadPath { file_index, position, error } => {
+                write!(f, "file {}: proof {}: {}", file_index, position, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProofShapeError {}
+
+// n and d are the expander parameters the challenge was generated with.
+pub fn check_commit_proofs(
+    proofs: &[Vec<CommitProof>],
+    challenge: &Challenge,
+    n: i64,
+    d: i64,
+) -> Result<(), ProofShapeError> {
+    if let Some(missing) = challenge.0.get(proofs.len()) {
+        return Err(ProofShapeError::MissingFile {
+            file_index: missing.file_index,
+        });
+    }
+    if proofs.len() > challenge.0.len() {
+        return Err(ProofShapeError::ExtraFiles {
+            expected: challenge.0.len(),
+            found: proofs.len(),
+        });
+    }
+
+    for (chal, file_proofs) in challenge.0.iter().zip(proofs) {
+        let file_index = chal.file_index;
+        let expected = chal.node_indices.len() + 1;
+        if file_proofs.len() != expected {
+            return Err(ProofShapeError::NodeCount {
+                file_index,
+                expected,
+                found: file_proofs.len(),
+            });
+        }
+
+        let mut index = chal.layer_selector;
+        for (position, proof) in file_proofs.iter().enumerate() {
+            let node = proof
+                .node
+                .as_ref()
+                .ok_or(ProofShapeError::MissingNode { file_index, position })?;
+            if i64::from(node.index) != index {
+                return Err(ProofShapeError::IndexMismatch {
+                    file_index,
+                    position,
+                    expected: index,
+                    found: node.index.into(),
+                });
+            }
+
+            let expected = if n > 0 && index >= n {
+                usize::try_from(d).unwrap_or(0).saturating_add(1)
+            } else {
+                0
+            };
+            if proof.parents.len() != expected {
+                return Err(ProofShapeError::ParentCount {
+                    file_index,
+                    position,
+                    expected,
+                    found: proof.parents.len(),
+                });
+            }
+
+            for mht in proof.node.iter().chain(&proof.parents) {
+                check_path_shape(mht).map_err(|error| ProofShapeError::BadPath { file_index, position, error })?;
+            }
+
+            // The next proof is for the parent picked by the challenge
+            if let Some(&selector) = chal.node_indices.get(position) {
+                let parent = usize::try_from(selector)
+                    .ok()
+                    .and_then(|s| proof.parents.get(s))
+                    .ok_or(ProofShapeError::InvalidSelector { file_index, position, selector })?;
+                index = parent.index.into();
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,750 @@
+use std::{fmt, mem, os::raw::c_int, ptr, slice};
+
+use crate::{
+    c_types::{ArrOfArr, CommitC, CommitProofC, MhtProofC, NodeType},
+    types::{Commit, CommitProof, MhtProof},
+};
+
+// Zero-copy views over arrays living in C memory.
+// A view borrows from whatever owns the memory, usually one of the guards
+// below, so it can not outlive it. Call to_vec() when an owned copy is needed.
+//
+// Pointers and lengths are checked once when a view is built, so a malformed
+// array coming back from Go is an error instead of undefined behaviour.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarshalError {
+    // A null pointer with a non-zero length
+    NullPointer { field: &'static str },
+    NegativeLength { field: &'static str, length: i64 },
+    // The array does not fit in the address space
+    TooLarge { field: &'static str },
+}
+
+impl fmt::Display for MarshalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarshalError::NullPointer { field } => {
+                write!(f, "{} is null but has a non-zero length", field)
+            }
+            MarshalError::NegativeLength { field, length } => {
+                write!(f, "{} has a negative length {}", field, length)
+            }
+            MarshalError::TooLarge { field } => write!(f, "{} is too large", field),
+        }
+    }
+}
+
+impl std::error::Error for MarshalError {}
+
+// Check ptr and len describe a slice we are allowed to build,
+// and add its size to the running total of bytes.
+fn check_array<T>(
+    ptr: *const T,
+    len: i64,
+    total: &mut usize,
+    field: &'static str,
+) -> Result<usize, MarshalError> {
+    if len < 0 {
+        return Err(MarshalError::NegativeLength { field, length: len });
+    }
+    let len = usize::try_from(len).map_err(|_| MarshalError::TooLarge { field })?;
+    if len > 0 && ptr.is_null() {
+        return Err(MarshalError::NullPointer { field });
+    }
+    *total = len
+        .checked_mul(mem::size_of::<T>())
+        .and_then(|size| total.checked_add(size))
+        .filter(|&size| size <= isize::MAX as usize)
+        .ok_or(MarshalError::TooLarge { field })?;
+    Ok(len)
+}
+
+// Same as slice::from_raw_parts but accepts a null pointer for an empty array,
+// which is how both sides pass empty arrays.
+unsafe fn raw_slice<'a, T>(ptr: *const T, len: usize) -> &'a [T] {
+    if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(ptr, len)
+    }
+}
+
+// Names reported in a MarshalError for each part of a [][]T
+type Fields = [&'static str; 3];
+const ARR_OF_ARR_FIELDS: Fields = ["main_array", "sub_array_lengths", "sub_array"];
+const ROOTS_FIELDS: Fields = ["roots", "sub_roots_lengths", "root"];
+const PATHS_FIELDS: Fields = ["paths", "paths_lengths", "path"];
+
+// [][]T in C memory, e.g. the challenge returned by Go
+pub struct ArrOfArrView<'a, T> {
+    rows: &'a [*mut T],
+    lengths: &'a [c_int],
+}
+
+// Derives would require T: Clone
+impl<T> Clone for ArrOfArrView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ArrOfArrView<'_, T> {}
+
+pub type I64MatrixView<'a> = ArrOfArrView<'a, i64>;
+
+impl<'a, T> ArrOfArrView<'a, T> {
+    /// # Safety
+    ///
+    /// Non-null pointers must point to as many valid elements as their
+    /// length says, and all of it must stay alive and unchanged for `'a`.
+    pub unsafe fn from_raw(
+        main_array: *const *mut T,
+        sub_array_lengths: *const c_int,
+        length: c_int,
+    ) -> Result<Self, MarshalError> {
+        Self::check(main_array, sub_array_lengths, length, &mut 0, ARR_OF_ARR_FIELDS)
+    }
+
+    unsafe fn check(
+        main_array: *const *mut T,
+        sub_array_lengths: *const c_int,
+        length: c_int,
+        total: &mut usize,
+        [main_field, lengths_field, sub_field]: Fields,
+    ) -> Result<Self, MarshalError> {
+        let length = check_array(main_array, length.into(), total, main_field)?;
+        check_array(sub_array_lengths, length as i64, total, lengths_field)?;
+
+        let rows = raw_slice(main_array, length);
+        let lengths = raw_slice(sub_array_lengths, length);
+        for (&row, &row_length) in rows.iter().zip(lengths) {
+            check_array(row, row_length.into(), total, sub_field)?;
+        }
+
+        Ok(ArrOfArrView { rows, lengths })
+    }
+
+    // For arrays that already went through from_raw
+    unsafe fn from_raw_unchecked(
+        main_array: *const *mut T,
+        sub_array_lengths: *const c_int,
+        length: c_int,
+    ) -> Self {
+        ArrOfArrView {
+            rows: raw_slice(main_array, length as usize),
+            lengths: raw_slice(sub_array_lengths, length as usize),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn get(&self, i: usize) -> Option<&'a [T]> {
+        let row = *self.rows.get(i)?;
+        let length = self.lengths[i] as usize;
+        // Checked in from_raw
+        Some(unsafe { raw_slice(row, length) })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'a [T]> + '_ {
+        (0..self.len()).filter_map(move |i| self.get(i))
+    }
+}
+
+impl<T: Clone> ArrOfArrView<'_, T> {
+    pub fn to_vec(&self) -> Vec<Vec<T>> {
+        self.iter().map(|row| row.to_vec()).collect()
+    }
+}
+
+impl<T> ArrOfArr<T> {
+    /// # Safety
+    ///
+    /// See `ArrOfArrView::from_raw`.
+    pub unsafe fn view(&self) -> Result<ArrOfArrView<'_, T>, MarshalError> {
+        ArrOfArrView::from_raw(self.main_array, self.sub_array_lengths, self.length)
+    }
+}
+
+// A single CommitC in C memory
+#[derive(Clone, Copy)]
+pub struct CommitCView<'a> {
+    file_index: i64,
+    roots: ArrOfArrView<'a, u8>,
+}
+
+impl<'a> CommitCView<'a> {
+    /// # Safety
+    ///
+    /// Non-null pointers in `commit` must point to as many valid elements as
+    /// their length says, and all of it must stay alive and unchanged for `'a`.
+    pub unsafe fn from_raw(commit: &'a CommitC) -> Result<Self, MarshalError> {
+        Self::check(commit, &mut 0)
+    }
+
+    unsafe fn check(commit: &'a CommitC, total: &mut usize) -> Result<Self, MarshalError> {
+        // The Go header puts roots_length before sub_roots_lengths,
+        // so CommitC can not embed an ArrOfArr, but the roots are one all the same.
+        let roots = ArrOfArrView::check(
+            commit.roots,
+            commit.sub_roots_lengths,
+            commit.roots_length,
+            total,
+            ROOTS_FIELDS,
+        )?;
+
+        Ok(CommitCView {
+            file_index: commit.file_index,
+            roots,
+        })
+    }
+
+    pub fn file_index(&self) -> i64 {
+        self.file_index
+    }
+
+    pub fn roots(&self) -> ArrOfArrView<'a, u8> {
+        self.roots
+    }
+
+    pub fn to_commit(&self) -> Commit {
+        Commit {
+            file_index: self.file_index,
+            roots: self.roots.to_vec(),
+        }
+    }
+}
+
+// []CommitC in C memory. Every commit is checked when the view is built,
+// the CommitCView of one is only made when it is asked for.
+#[derive(Clone, Copy)]
+pub struct CommitCArrayView<'a> {
+    commits: &'a [CommitC],
+}
+
+impl<'a> CommitCArrayView<'a> {
+    /// # Safety
+    ///
+    /// `commits`, when not null, must point to `length` `CommitC`, each
+    /// satisfying the contract of `CommitCView::from_raw`.
+    pub unsafe fn from_raw(commits: *const CommitC, length: i64) -> Result<Self, MarshalError> {
+        let mut total = 0;
+        let length = check_array(commits, length, &mut total, "commits")?;
+        let commits = raw_slice(commits, length);
+        for commit in commits {
+            CommitCView::check(commit, &mut total)?;
+        }
+
+        Ok(CommitCArrayView { commits })
+    }
+
+    pub fn len(&self) -> usize {
+        self.commits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commits.is_empty()
+    }
+
+    pub fn get(&self, i: usize) -> Option<CommitCView<'a>> {
+        self.commits.get(i).map(view_checked_commit)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = CommitCView<'a>> + '_ {
+        self.commits.iter().map(view_checked_commit)
+    }
+
+    pub fn to_vec(&self) -> Vec<Commit> {
+        self.iter().map(|commit| commit.to_commit()).collect()
+    }
+}
+
+// For the commits of a CommitCArrayView, checked in from_raw
+fn view_checked_commit(commit: &CommitC) -> CommitCView<'_> {
+    CommitCView {
+        file_index: commit.file_index,
+        roots: unsafe { ArrOfArrView::from_raw_unchecked(commit.roots, commit.sub_roots_lengths, commit.roots_length) },
+    }
+}
+
+// A single MhtProofC in C memory
+#[derive(Clone, Copy)]
+pub struct MhtProofCView<'a> {
+    index: NodeType,
+    label: &'a [u8],
+    paths: ArrOfArrView<'a, u8>,
+    locs: &'a [u8],
+}
+
+impl<'a> MhtProofCView<'a> {
+    /// # Safety
+    ///
+    /// Non-null pointers in `proof` must point to as many valid elements as
+    /// their length says, and all of it must stay alive and unchanged for `'a`.
+    pub unsafe fn from_raw(proof: &'a MhtProofC) -> Result<Self, MarshalError> {
+        Self::check(proof, &mut 0)
+    }
+
+    unsafe fn check(proof: &'a MhtProofC, total: &mut usize) -> Result<Self, MarshalError> {
+        let label = check_array(proof.label, proof.label_length.into(), total, "label")?;
+        let paths = ArrOfArrView::check(
+            proof.paths,
+            proof.sub_paths_lengths,
+            proof.path_length,
+            total,
+            PATHS_FIELDS,
+        )?;
+        let locs = check_array(proof.locs, proof.locs_length.into(), total, "locs")?;
+
+        Ok(MhtProofCView {
+            index: proof.index,
+            label: raw_slice(proof.label, label),
+            paths,
+            locs: raw_slice(proof.locs, locs),
+        })
+    }
+
+    pub fn index(&self) -> NodeType {
+        self.index
+    }
+
+    pub fn label(&self) -> &'a [u8] {
+        self.label
+    }
+
+    pub fn paths(&self) -> ArrOfArrView<'a, u8> {
+        self.paths
+    }
+
+    pub fn locs(&self) -> &'a [u8] {
+        self.locs
+    }
+
+    pub fn to_proof(&self) -> MhtProof {
+        MhtProof {
+            index: self.index,
+            label: self.label.to_vec(),
+            paths: self.paths.to_vec(),
+            locs: self.locs.to_vec(),
+        }
+    }
+}
+
+// A single CommitProofC in C memory, a null node is no node
+#[derive(Clone)]
+pub struct CommitProofCView<'a> {
+    node: Option<MhtProofCView<'a>>,
+    parents: Vec<MhtProofCView<'a>>,
+}
+
+impl<'a> CommitProofCView<'a> {
+    /// # Safety
+    ///
+    /// The node and every parent, when not null, must satisfy the contract
+    /// of `MhtProofCView::from_raw`, and `parents` must point to
+    /// `parents_length` of them.
+    pub unsafe fn from_raw(proof: &'a CommitProofC) -> Result<Self, MarshalError> {
+        Self::check(proof, &mut 0)
+    }
+
+    unsafe fn check(proof: &'a CommitProofC, total: &mut usize) -> Result<Self, MarshalError> {
+        let node = match proof.node.as_ref() {
+            Some(node) => Some(MhtProofCView::check(node, total)?),
+            None => None,
+        };
+        let length = check_array(proof.parents, proof.parents_length.into(), total, "parents")?;
+        let parents = raw_slice(proof.parents, length)
+            .iter()
+            .map(|&parent| match parent.as_ref() {
+                Some(parent) => MhtProofCView::check(parent, total),
+                None => Err(MarshalError::NullPointer { field: "parent" }),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(CommitProofCView { node, parents })
+    }
+
+    pub fn node(&self) -> Option<MhtProofCView<'a>> {
+        self.node
+    }
+
+    pub fn parents(&self) -> &[MhtProofCView<'a>] {
+        &self.parents
+    }
+
+    pub fn to_proof(&self) -> CommitProof {
+        CommitProof {
+            node: self.node.map(|node| node.to_proof()),
+            parents: self.parents.iter().map(MhtProofCView::to_proof).collect(),
+        }
+    }
+}
+
+// **CommitProofC in C memory, one array of proofs per challenged file.
+// C only gets the number of files, how many proofs each has follows
+// from the challenge, so the lengths are passed separately.
+#[derive(Clone)]
+pub struct CommitProofCArrayView<'a> {
+    files: Vec<Vec<CommitProofCView<'a>>>,
+}
+
+impl<'a> CommitProofCArrayView<'a> {
+    /// # Safety
+    ///
+    /// `files`, when not null, must point to `lengths.len()` arrays, the
+    /// i-th holding `lengths[i]` `CommitProofC` that each satisfy the
+    /// contract of `CommitProofCView::from_raw`.
+    pub unsafe fn from_raw(files: *const *mut CommitProofC, lengths: &[i64]) -> Result<Self, MarshalError> {
+        let mut total = 0;
+        let length = check_array(files, lengths.len() as i64, &mut total, "commit_proofs")?;
+        let files = raw_slice(files, length)
+            .iter()
+            .zip(lengths)
+            .map(|(&file, &length)| {
+                let length = check_array(file, length, &mut total, "proofs")?;
+                raw_slice(file, length)
+                    .iter()
+                    .map(|proof| CommitProofCView::check(proof, &mut total))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(CommitProofCArrayView { files })
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn get(&self, i: usize) -> Option<&[CommitProofCView<'a>]> {
+        self.files.get(i).map(Vec::as_slice)
+    }
+
+    pub fn to_vec(&self) -> Vec<Vec<CommitProof>> {
+        self.files
+            .iter()
+            .map(|file| file.iter().map(CommitProofCView::to_proof).collect())
+            .collect()
+    }
+}
+
+// Owns a [][]T built on the Rust side and the ArrOfArr pointing into it,
+// e.g. a challenge passed to Go. The pointers stay valid as long as it lives.
+// Empty arrays are passed as null pointers.
+pub struct ArrOfArrBuf<T> {
+    raw: ArrOfArr<T>,
+    _rows: Vec<Vec<T>>,
+    _main_array: Vec<*mut T>,
+    _lengths: Vec<c_int>,
+}
+
+impl<T> ArrOfArrBuf<T> {
+    pub fn new(mut rows: Vec<Vec<T>>) -> Self {
+        let mut main_array: Vec<*mut T> = rows
+            .iter_mut()
+            .map(|row| if row.is_empty() { ptr::null_mut() } else { row.as_mut_ptr() })
+            .collect();
+        let mut lengths: Vec<c_int> = rows
+            .iter()
+            .map(|row| c_int::try_from(row.len()).expect("sub array too long for C"))
+            .collect();
+
+        let raw = if rows.is_empty() {
+            ArrOfArr {
+                main_array: ptr::null_mut(),
+                sub_array_lengths: ptr::null_mut(),
+                length: 0,
+            }
+        } else {
+            ArrOfArr {
+                main_array: main_array.as_mut_ptr(),
+                sub_array_lengths: lengths.as_mut_ptr(),
+                length: c_int::try_from(rows.len()).expect("array too long for C"),
+            }
+        };
+
+        ArrOfArrBuf {
+            raw,
+            _rows: rows,
+            _main_array: main_array,
+            _lengths: lengths,
+        }
+    }
+
+    pub fn as_raw(&self) -> &ArrOfArr<T> {
+        &self.raw
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut ArrOfArr<T> {
+        &mut self.raw
+    }
+
+    pub fn view(&self) -> ArrOfArrView<'_, T> {
+        // Built from Vecs in new
+        unsafe {
+            ArrOfArrView::from_raw_unchecked(
+                self.raw.main_array,
+                self.raw.sub_array_lengths,
+                self.raw.length,
+            )
+        }
+    }
+
+    // Leak the memory, for arrays handed over to C for good
+    pub fn into_raw(self) -> ArrOfArr<T> {
+        let raw = ArrOfArr {
+            main_array: self.raw.main_array,
+            sub_array_lengths: self.raw.sub_array_lengths,
+            length: self.raw.length,
+        };
+        mem::forget(self);
+        raw
+    }
+}
+
+impl<T> From<Vec<Vec<T>>> for ArrOfArrBuf<T> {
+    fn from(rows: Vec<Vec<T>>) -> Self {
+        ArrOfArrBuf::new(rows)
+    }
+}
+
+// Owns a [][]T allocated with C.malloc on the Go side
+// and frees it when dropped.
+pub struct ArrOfArrGuard<T> {
+    raw: ArrOfArr<T>,
+}
+
+pub type I64ArrOfArrGuard = ArrOfArrGuard<i64>;
+
+impl<T> ArrOfArrGuard<T> {
+    /// # Safety
+    ///
+    /// Besides the contract of `ArrOfArrView::from_raw`, every pointer must
+    /// come from `malloc` and must not be freed by anyone else.
+    /// The memory is not freed when the arrays are malformed.
+    pub unsafe fn from_raw(
+        main_array: *mut *mut T,
+        sub_array_lengths: *mut c_int,
+        length: c_int,
+    ) -> Result<Self, MarshalError> {
+        ArrOfArrView::from_raw(main_array, sub_array_lengths, length)?;
+        Ok(ArrOfArrGuard {
+            raw: ArrOfArr {
+                main_array,
+                sub_array_lengths,
+                length,
+            },
+        })
+    }
+
+    pub fn view(&self) -> ArrOfArrView<'_, T> {
+        // Checked in from_raw
+        unsafe {
+            ArrOfArrView::from_raw_unchecked(
+                self.raw.main_array,
+                self.raw.sub_array_lengths,
+                self.raw.length,
+            )
+        }
+    }
+}
+
+impl<T> Drop for ArrOfArrGuard<T> {
+    fn drop(&mut self) {
+        unsafe {
+            for &row in raw_slice(self.raw.main_array, self.raw.length as usize) {
+                libc::free(row as *mut libc::c_void);
+            }
+            libc::free(self.raw.main_array as *mut libc::c_void);
+            libc::free(self.raw.sub_array_lengths as *mut libc::c_void);
+        }
+    }
+}
+
+// Owns a []CommitC built on the Rust side and the roots it points into.
+pub struct CommitCArrayGuard {
+    commits: Vec<CommitC>,
+    _roots: Vec<ArrOfArrBuf<u8>>,
+}
+
+impl CommitCArrayGuard {
+    pub fn new(commits: &[Commit]) -> Self {
+        let roots: Vec<ArrOfArrBuf<u8>> = commits
+            .iter()
+            .map(|commit| ArrOfArrBuf::new(commit.roots.clone()))
+            .collect();
+        let commits = commits
+            .iter()
+            .zip(&roots)
+            .map(|(commit, roots)| {
+                let roots = roots.as_raw();
+                CommitC {
+                    file_index: commit.file_index,
+                    roots: roots.main_array,
+                    roots_length: roots.length,
+                    sub_roots_lengths: roots.sub_array_lengths,
+                }
+            })
+            .collect();
+
+        CommitCArrayGuard {
+            commits,
+            _roots: roots,
+        }
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut CommitC {
+        self.commits.as_mut_ptr()
+    }
+
+    pub fn len(&self) -> usize {
+        self.commits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commits.is_empty()
+    }
+
+    pub fn view(&self) -> CommitCArrayView<'_> {
+        let commits = self.commits.as_ptr();
+        unsafe { CommitCArrayView::from_raw(commits, self.commits.len() as i64) }
+            .expect("commits built in new are well formed")
+    }
+}
+
+// Owns a MhtProofC built on the Rust side and the arrays it points into.
+// It is kept behind a raw pointer rather than a Box so its address stays
+// put, and the pointers to it stay valid, when the buffer is moved.
+struct MhtProofCBuf {
+    raw: *mut MhtProofC,
+    _label: Vec<u8>,
+    _paths: ArrOfArrBuf<u8>,
+    _locs: Vec<u8>,
+}
+
+impl MhtProofCBuf {
+    fn new(proof: &MhtProof) -> Self {
+        let mut label = proof.label.clone();
+        let mut locs = proof.locs.clone();
+        let paths = ArrOfArrBuf::new(proof.paths.clone());
+        let raw = Box::into_raw(Box::new(MhtProofC {
+            index: proof.index,
+            label: vec_ptr(&mut label),
+            label_length: c_int::try_from(label.len()).expect("label too long for C"),
+            paths: paths.as_raw().main_array,
+            sub_paths_lengths: paths.as_raw().sub_array_lengths,
+            path_length: paths.as_raw().length,
+            locs: vec_ptr(&mut locs),
+            locs_length: c_int::try_from(locs.len()).expect("locs too long for C"),
+        }));
+
+        MhtProofCBuf {
+            raw,
+            _label: label,
+            _paths: paths,
+            _locs: locs,
+        }
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut MhtProofC {
+        self.raw
+    }
+}
+
+impl Drop for MhtProofCBuf {
+    fn drop(&mut self) {
+        // From Box::into_raw in new
+        drop(unsafe { Box::from_raw(self.raw) });
+    }
+}
+
+// Null for an empty Vec, like ArrOfArrBuf
+fn vec_ptr<T>(v: &mut Vec<T>) -> *mut T {
+    if v.is_empty() {
+        ptr::null_mut()
+    } else {
+        v.as_mut_ptr()
+    }
+}
+
+// Owns the **CommitProofC built on the Rust side for a miner's commit
+// proofs, and everything it points into.
+pub struct CommitProofCArrayGuard {
+    files: Vec<*mut CommitProofC>,
+    lengths: Vec<i64>,
+    _proofs: Vec<Vec<CommitProofC>>,
+    _parents: Vec<Vec<*mut MhtProofC>>,
+    _mht_proofs: Vec<MhtProofCBuf>,
+}
+
+impl CommitProofCArrayGuard {
+    pub fn new(commit_proofs: &[Vec<CommitProof>]) -> Self {
+        let mut mht_proofs = Vec::new();
+        let mut all_parents = Vec::new();
+        let mut mht = |proof: &MhtProof| {
+            let mut buf = MhtProofCBuf::new(proof);
+            let ptr = buf.as_mut_ptr();
+            mht_proofs.push(buf);
+            ptr
+        };
+
+        let mut proofs: Vec<Vec<CommitProofC>> = commit_proofs
+            .iter()
+            .map(|file| {
+                file.iter()
+                    .map(|proof| {
+                        let node = proof.node.as_ref().map_or(ptr::null_mut(), &mut mht);
+                        let mut parents: Vec<*mut MhtProofC> = proof.parents.iter().map(&mut mht).collect();
+                        let raw = CommitProofC {
+                            node,
+                            parents: vec_ptr(&mut parents),
+                            parents_length: c_int::try_from(parents.len()).expect("too many parents for C"),
+                        };
+                        all_parents.push(parents);
+                        raw
+                    })
+                    .collect()
+            })
+            .collect();
+        let files = proofs.iter_mut().map(vec_ptr).collect();
+        let lengths = proofs.iter().map(|file| file.len() as i64).collect();
+
+        CommitProofCArrayGuard {
+            files,
+            lengths,
+            _proofs: proofs,
+            _parents: all_parents,
+            _mht_proofs: mht_proofs,
+        }
+    }
+
+    // The **CommitProofC to pass to Go, null when there are no files
+    pub fn as_mut_ptr(&mut self) -> *mut *mut CommitProofC {
+        vec_ptr(&mut self.files)
+    }
+
+    // Number of files
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn view(&self) -> CommitProofCArrayView<'_> {
+        let files = if self.files.is_empty() { ptr::null() } else { self.files.as_ptr() };
+        unsafe { CommitProofCArrayView::from_raw(files, &self.lengths) }
+            .expect("commit proofs built in new are well formed")
+    }
+}
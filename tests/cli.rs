@@ -0,0 +1,89 @@
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+
+use cess_pois_rust_ffi::{
+    params::PoisParams,
+    testing::{make_commits, shaped_proofs},
+    types::{Challenge, RsaKey},
+};
+
+// Runs pois-cli as operators do, against the prebuilt Go library.
+
+fn pois_cli(args: &[&str], dir: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_pois-cli"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("failed to run pois-cli")
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "pois-cli failed: {}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn go_library() -> String {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("cgo/main.so").display().to_string()
+}
+
+#[test]
+fn test_cli_keygen_and_params() {
+    let dir = tempfile::tempdir().unwrap();
+
+    stdout(&pois_cli(&["keygen", "--out", "key.json"], dir.path()));
+    let key: RsaKey = serde_json::from_str(&fs::read_to_string(dir.path().join("key.json")).unwrap()).unwrap();
+    assert!(key.n.bits() > 2000);
+
+    let params: PoisParams = toml::from_str(&stdout(&pois_cli(&["params", "--preset", "mainnet"], dir.path()))).unwrap();
+    assert_eq!(params, PoisParams::mainnet());
+
+    fs::write(dir.path().join("params.toml"), "preset = \"testnet\"\nd = 32\n").unwrap();
+    let params: PoisParams = toml::from_str(&stdout(&pois_cli(&["params", "--config", "params.toml"], dir.path()))).unwrap();
+    assert_eq!(params, PoisParams { d: 32, ..PoisParams::testnet() });
+
+    let output = pois_cli(&["params", "--preset", "localnet"], dir.path());
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("localnet"));
+}
+
+#[test]
+fn test_cli_challenge_verify_inspect() {
+    let dir = tempfile::tempdir().unwrap();
+    let lib = go_library();
+    let params = PoisParams::devnet();
+
+    stdout(&pois_cli(&["keygen", "--out", "key.json"], dir.path()));
+    let commits = make_commits(params.k, 4, 1);
+    fs::write(dir.path().join("commits.json"), serde_json::to_string(&commits).unwrap()).unwrap();
+
+    let verifier = ["--key", "key.json", "--id", "cli miner", "--lib", lib.as_str()];
+    let mut args = vec!["challenge"];
+    args.extend(verifier);
+    args.extend(["--commits", "commits.json", "--out", "chal.json"]);
+    stdout(&pois_cli(&args, dir.path()));
+
+    let rows: Vec<Vec<i64>> = serde_json::from_str(&fs::read_to_string(dir.path().join("chal.json")).unwrap()).unwrap();
+    let challenge = Challenge::try_from(rows).unwrap();
+    assert_eq!(challenge.0.len(), commits.len());
+
+    let proofs = shaped_proofs(&challenge, params.n, params.d);
+    fs::write(dir.path().join("proofs.json"), serde_json::to_string(&proofs).unwrap()).unwrap();
+
+    // The prebuilt library reports no verdict, so even well shaped proofs
+    // must not come back as verified
+    let mut args = vec!["verify"];
+    args.extend(verifier);
+    args.extend(["--proofs", "proofs.json", "--challenge", "chal.json"]);
+    let output = pois_cli(&args, dir.path());
+    assert!(!output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("ok"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not wired"));
+    assert!(stdout(&pois_cli(&["verify", "--help"], dir.path())).contains("always fails"));
+
+    let summary = stdout(&pois_cli(&["inspect", "proofs.json"], dir.path()));
+    assert_eq!(summary.matches("proofs ").count(), proofs.len());
+    assert!(summary.contains(&format!("proofs 0 ({} nodes):", proofs[0].len())));
+}